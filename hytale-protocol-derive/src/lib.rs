@@ -0,0 +1,547 @@
+//! Derive macros for `hytale-protocol`.
+//!
+//! `#[derive(HytalePacket)]` generates `PacketRead` and `PacketWrite` for a struct
+//! following the layout used by the Hytale server:
+//!
+//! 1. nullBits: one bit per `nullable` field, in declaration order.
+//! 2. Inline fields (everything not marked `offset`), in declaration order.
+//! 3. Offset table: one `i32` LE per `offset` field, relative to the variable block, `-1` when null.
+//! 4. Variable block: the `offset` fields, written in declaration order and read back
+//!    through `VarBlockReader` at their offsets.
+//!
+//! Field attributes (`#[hytale(...)]`):
+//! - `nullable`: the field is an `Option<T>` and gets a bit in nullBits.
+//!   Fixed-size inline fields are zero filled when `None`.
+//! - `offset`: the field lives in the variable block and is indexed by the offset table.
+//! - `var_string`: `String` prefixed by its VarInt length.
+//! - `fixed_size = N`: `String` or `Vec<u8>` padded with zeros to exactly `N` bytes, `N` being
+//!   a literal or a `usize` constant. Longer values are truncated, strings on a char boundary.
+//! - `le` / `be`: byte order of numbers and UUIDs (numbers default to LE, UUIDs to BE).
+//!
+//! Other supported types are the primitives, `bool`, `Uuid`, `Vec<u8>` and `Bytes`
+//! (VarInt length + bytes, `Bytes` is read without copying), `Vec<T>` (VarInt count + elements)
+//! and any type implementing `PacketRead` + `PacketWrite`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, Error, Expr, Fields, GenericArgument, Ident, PathArguments, Result, Type,
+    parse_macro_input,
+};
+
+#[proc_macro_derive(HytalePacket, attributes(hytale))]
+pub fn derive_hytale_packet(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Order {
+    Le,
+    Be,
+}
+
+enum Kind {
+    /// Primitive number or bool, identified by its type name.
+    Primitive(Ident, Order),
+    Uuid(Order),
+    VarString,
+    FixedString(Expr),
+    VarBytes,
+    /// `Bytes` blob, sliced out of the packet without copying.
+    Blob,
+    FixedBytes(Expr),
+    List(Type),
+    Nested(Type),
+}
+
+impl Kind {
+    /// Size in bytes for kinds that always take the same room on the wire.
+    fn fixed_size(&self) -> Option<TokenStream2> {
+        match self {
+            Kind::Primitive(ty, _) => {
+                let size: usize = match ty.to_string().as_str() {
+                    "u8" | "i8" | "bool" => 1,
+                    "u16" | "i16" => 2,
+                    "u32" | "i32" | "f32" => 4,
+                    _ => 8,
+                };
+                Some(quote! { #size })
+            }
+            Kind::Uuid(_) => Some(quote! { 16usize }),
+            Kind::FixedString(n) | Kind::FixedBytes(n) => Some(quote! { (#n) }),
+            _ => None,
+        }
+    }
+}
+
+struct Field {
+    ident: Ident,
+    nullable: bool,
+    offset: bool,
+    kind: Kind,
+}
+
+#[derive(Default)]
+struct Attrs {
+    nullable: bool,
+    offset: bool,
+    var_string: bool,
+    fixed_size: Option<Expr>,
+    order: Option<Order>,
+}
+
+fn parse_attrs(field: &syn::Field) -> Result<Attrs> {
+    let mut attrs = Attrs::default();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("hytale")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("nullable") {
+                attrs.nullable = true;
+            } else if meta.path.is_ident("offset") {
+                attrs.offset = true;
+            } else if meta.path.is_ident("var_string") {
+                attrs.var_string = true;
+            } else if meta.path.is_ident("fixed_size") {
+                attrs.fixed_size = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("le") {
+                attrs.order = Some(Order::Le);
+            } else if meta.path.is_ident("be") {
+                attrs.order = Some(Order::Be);
+            } else {
+                return Err(meta.error("unknown hytale attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// Returns the single generic argument of `Wrapper<T>` if `ty` is a `Wrapper`.
+fn generic_arg<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+fn type_name(ty: &Type) -> Option<Ident> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    segment.arguments.is_empty().then(|| segment.ident.clone())
+}
+
+fn classify(ty: &Type, attrs: &Attrs) -> Result<Kind> {
+    let order = attrs.order;
+    if let Some(inner) = generic_arg(ty, "Vec") {
+        let is_bytes = type_name(inner).is_some_and(|name| name == "u8");
+        return Ok(match (is_bytes, attrs.fixed_size.clone()) {
+            (true, Some(n)) => Kind::FixedBytes(n),
+            (true, None) => Kind::VarBytes,
+            (false, None) => Kind::List(inner.clone()),
+            (false, Some(_)) => {
+                return Err(Error::new_spanned(
+                    ty,
+                    "fixed_size only applies to String and Vec<u8>",
+                ));
+            }
+        });
+    }
+
+    let Some(name) = type_name(ty) else {
+        return Ok(Kind::Nested(ty.clone()));
+    };
+    match name.to_string().as_str() {
+        "u8" | "i8" | "bool" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "f32" | "f64" => {
+            Ok(Kind::Primitive(name, order.unwrap_or(Order::Le)))
+        }
+        "Uuid" => Ok(Kind::Uuid(order.unwrap_or(Order::Be))),
+        "Bytes" if attrs.fixed_size.is_none() => Ok(Kind::Blob),
+        "String" => match (attrs.var_string, attrs.fixed_size.clone()) {
+            (true, None) => Ok(Kind::VarString),
+            (false, Some(n)) => Ok(Kind::FixedString(n)),
+            _ => Err(Error::new_spanned(
+                ty,
+                "String fields need exactly one of #[hytale(var_string)] or #[hytale(fixed_size = N)]",
+            )),
+        },
+        _ => Ok(Kind::Nested(ty.clone())),
+    }
+}
+
+fn parse_field(field: &syn::Field) -> Result<Field> {
+    let ident = field.ident.clone().expect("named field");
+    let attrs = parse_attrs(field)?;
+    let option_inner = generic_arg(&field.ty, "Option");
+    let ty = match (attrs.nullable, option_inner) {
+        (true, Some(inner)) => inner,
+        (true, None) => {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "nullable fields must be an Option<T>",
+            ));
+        }
+        (false, Some(_)) => {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "Option fields must be marked #[hytale(nullable)]",
+            ));
+        }
+        (false, None) => &field.ty,
+    };
+    let kind = classify(ty, &attrs)?;
+    let ordered = match &kind {
+        Kind::Primitive(ty, _) => ty != "bool",
+        Kind::Uuid(_) => true,
+        _ => false,
+    };
+    if attrs.order.is_some() && !ordered {
+        return Err(Error::new_spanned(
+            ty,
+            "le / be only apply to numbers and UUIDs",
+        ));
+    }
+    match kind {
+        Kind::VarString | Kind::FixedString(_) | Kind::FixedBytes(_) => {}
+        _ if attrs.var_string => {
+            return Err(Error::new_spanned(ty, "var_string only applies to String"));
+        }
+        _ if attrs.fixed_size.is_some() => {
+            return Err(Error::new_spanned(
+                ty,
+                "fixed_size only applies to String and Vec<u8>",
+            ));
+        }
+        _ => {}
+    }
+    Ok(Field {
+        ident,
+        nullable: attrs.nullable,
+        offset: attrs.offset,
+        kind,
+    })
+}
+
+fn read_value(kind: &Kind, buf: &TokenStream2) -> TokenStream2 {
+    match kind {
+        Kind::Primitive(ty, order) => {
+            let method = match (ty.to_string().as_str(), order) {
+                ("u8" | "i8" | "bool", _) => format_ident!("read_{}", ty),
+                (_, Order::Le) => format_ident!("read_{}_le", ty),
+                (_, Order::Be) => format_ident!("read_{}_be", ty),
+            };
+            quote! { #buf.#method()? }
+        }
+        Kind::Uuid(Order::Be) => quote! { #buf.read_uuid_be()? },
+        Kind::Uuid(Order::Le) => quote! { #buf.read_uuid_le()? },
+        Kind::VarString => quote! { #buf.read_var_string()? },
+        Kind::FixedString(n) => quote! {
+            ::std::string::String::from_utf8(#buf.read_bytes(#n)?.to_vec())?
+                .trim_end_matches('\0')
+                .to_string()
+        },
+        Kind::VarBytes => quote! {
+            {
+                let len = #buf.read_count(1)?;
+                #buf.read_bytes(len)?.to_vec()
+            }
+        },
+        Kind::Blob => quote! {
+            {
+                let len = #buf.read_count(1)?;
+                #buf.read_bytes(len)?
+            }
+        },
+        Kind::FixedBytes(n) => quote! { #buf.read_bytes(#n)?.to_vec() },
+        Kind::List(ty) => quote! {
+            {
+                let count = #buf.read_count(1)?;
+                let mut items = ::std::vec::Vec::with_capacity(count);
+                for _ in 0..count {
+                    items.push(<#ty as ::hytale_protocol::codec::PacketRead>::read(#buf)?);
+                }
+                items
+            }
+        },
+        Kind::Nested(ty) => quote! { <#ty as ::hytale_protocol::codec::PacketRead>::read(#buf)? },
+    }
+}
+
+fn write_value(kind: &Kind, value: &TokenStream2, buf: &TokenStream2) -> TokenStream2 {
+    let codec = quote! { ::hytale_protocol::codec };
+    let buf_mut = quote! { ::hytale_protocol::__private::bytes::BufMut };
+    match kind {
+        Kind::Primitive(ty, order) => match (ty.to_string().as_str(), order) {
+            ("bool", _) => quote! { #buf_mut::put_u8(#buf, if *#value { 1 } else { 0 }); },
+            ("u8" | "i8", _) => {
+                let method = format_ident!("put_{}", ty);
+                quote! { #buf_mut::#method(#buf, *#value); }
+            }
+            (_, Order::Le) => {
+                let method = format_ident!("put_{}_le", ty);
+                quote! { #buf_mut::#method(#buf, *#value); }
+            }
+            (_, Order::Be) => {
+                let method = format_ident!("put_{}", ty);
+                quote! { #buf_mut::#method(#buf, *#value); }
+            }
+        },
+        Kind::Uuid(Order::Be) => quote! { #codec::write_uuid(#buf, #value); },
+        Kind::Uuid(Order::Le) => quote! {
+            #buf_mut::put_u64_le(#buf, (#value.as_u128() >> 64) as u64);
+            #buf_mut::put_u64_le(#buf, #value.as_u128() as u64);
+        },
+        Kind::VarString => quote! { #codec::write_string(#buf, #value); },
+        Kind::FixedString(n) => quote! {
+            {
+                // Cut on a char boundary, not in the middle of a UTF-8 sequence
+                let value: &str = #value;
+                let mut len = value.len().min(#n);
+                while !value.is_char_boundary(len) {
+                    len -= 1;
+                }
+                #buf_mut::put_slice(#buf, &value.as_bytes()[..len]);
+                #buf_mut::put_bytes(#buf, 0, (#n) - len);
+            }
+        },
+        Kind::FixedBytes(n) => quote! {
+            {
+                let bytes: &[u8] = #value.as_ref();
+                let len = bytes.len().min(#n);
+                #buf_mut::put_slice(#buf, &bytes[..len]);
+                #buf_mut::put_bytes(#buf, 0, (#n) - len);
+            }
+        },
+        Kind::VarBytes | Kind::Blob => quote! {
+            #codec::write_varint(#buf, #value.len() as i32);
+            #buf_mut::put_slice(#buf, #value);
+        },
+        Kind::List(_) => quote! {
+            #codec::write_varint(#buf, #value.len() as i32);
+            for item in #value.iter() {
+                #codec::PacketWrite::write(item, #buf);
+            }
+        },
+        Kind::Nested(_) => quote! { #codec::PacketWrite::write(#value, #buf); },
+    }
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input,
+            "HytalePacket can only be derived for structs",
+        ));
+    };
+    let fields = match &data.fields {
+        Fields::Named(named) => named
+            .named
+            .iter()
+            .map(parse_field)
+            .collect::<Result<Vec<_>>>()?,
+        Fields::Unit => Vec::new(),
+        Fields::Unnamed(_) => {
+            return Err(Error::new_spanned(
+                &input,
+                "HytalePacket needs named fields",
+            ));
+        }
+    };
+
+    // Assign null bits and offset slots in declaration order
+    let mut null_bit = Vec::new();
+    let mut offset_slot = Vec::new();
+    let (mut bits, mut slots) = (0usize, 0usize);
+    for field in &fields {
+        null_bit.push(field.nullable.then(|| {
+            bits += 1;
+            bits - 1
+        }));
+        offset_slot.push(field.offset.then(|| {
+            slots += 1;
+            slots - 1
+        }));
+    }
+    let null_bytes = bits.div_ceil(8);
+
+    let read_buf = quote! { buf };
+    let write_buf = quote! { buf };
+    let var_buf = quote! { (&mut var_block) };
+
+    let mut read_inline = Vec::new();
+    let mut read_var = Vec::new();
+    let mut write_null_bits = Vec::new();
+    let mut write_inline = Vec::new();
+    let mut write_var = Vec::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let ident = &field.ident;
+        let present = null_bit[i].map(|bit| {
+            let byte = bit / 8;
+            let mask = 1u8 << (bit % 8);
+            quote! { (null_bits[#byte] & #mask) != 0 }
+        });
+
+        if let Some(bit) = null_bit[i] {
+            let byte = bit / 8;
+            let mask = 1u8 << (bit % 8);
+            write_null_bits.push(quote! {
+                if self.#ident.is_some() {
+                    null_bits[#byte] |= #mask;
+                }
+            });
+        }
+
+        let reads = if field.offset {
+            &mut read_var
+        } else {
+            &mut read_inline
+        };
+        // Errors carry the field name and where reading stopped
+        let field_name = ident.to_string();
+        let field_name = field_name.trim_start_matches("r#");
+        let value = read_value(&field.kind, &read_buf);
+        let named = quote! {
+            buf.field(#field_name, |buf: &mut ::hytale_protocol::codec::PacketBuffer| {
+                let value = #value;
+                Ok(value)
+            })
+        };
+        let value = match offset_slot[i] {
+            Some(slot) => quote! {
+                var_block.read_field(#slot, |buf: &mut ::hytale_protocol::codec::PacketBuffer| #named)?
+            },
+            None => quote! { #named? },
+        };
+        reads.push(match (&present, field.kind.fixed_size()) {
+            // Fixed-size nullable fields are always on the wire, zeroed when null
+            (Some(present), Some(_)) if !field.offset => quote! {
+                let #ident = {
+                    let value = #value;
+                    if #present { Some(value) } else { None }
+                };
+            },
+            (Some(present), _) => quote! {
+                let #ident = if #present { Some(#value) } else { None };
+            },
+            (None, _) => quote! { let #ident = #value; },
+        });
+
+        let value = quote! { value };
+        if let Some(slot) = offset_slot[i] {
+            let write = write_value(&field.kind, &value, &var_buf);
+            let record = quote! { offsets[#slot] = var_block.len() as i32; };
+            write_var.push(if field.nullable {
+                quote! {
+                    if let Some(value) = &self.#ident {
+                        #record
+                        #write
+                    }
+                }
+            } else {
+                quote! {
+                    {
+                        let value = &self.#ident;
+                        #record
+                        #write
+                    }
+                }
+            });
+        } else {
+            let write = write_value(&field.kind, &value, &write_buf);
+            write_inline.push(match (field.nullable, field.kind.fixed_size()) {
+                (true, Some(size)) => quote! {
+                    match &self.#ident {
+                        Some(value) => { #write }
+                        None => ::hytale_protocol::__private::bytes::BufMut::put_bytes(buf, 0, #size),
+                    }
+                },
+                (true, None) => quote! {
+                    if let Some(value) = &self.#ident {
+                        #write
+                    }
+                },
+                (false, _) => quote! {
+                    {
+                        let value = &self.#ident;
+                        #write
+                    }
+                },
+            });
+        }
+    }
+
+    let idents = fields.iter().map(|f| &f.ident);
+    let construct = match &data.fields {
+        Fields::Unit => quote! { Self },
+        _ => quote! { Self { #(#idents),* } },
+    };
+
+    let read_null_bits =
+        (null_bytes > 0).then(|| quote! { let null_bits = buf.read_bytes(#null_bytes)?; });
+    let read_offsets = (slots > 0).then(|| {
+        quote! {
+            let mut var_block = ::hytale_protocol::codec::VarBlockReader::new(buf, #slots)?;
+        }
+    });
+    let finish_var = (slots > 0).then(|| quote! { var_block.finish(buf); });
+
+    let write_header = (null_bytes > 0).then(|| {
+        quote! {
+            let mut null_bits = [0u8; #null_bytes];
+            #(#write_null_bits)*
+            ::hytale_protocol::__private::bytes::BufMut::put_slice(buf, &null_bits);
+        }
+    });
+    let write_offsets = (slots > 0).then(|| {
+        quote! {
+            let mut var_block = ::hytale_protocol::__private::bytes::BytesMut::new();
+            let mut offsets = [-1i32; #slots];
+            #(#write_var)*
+            for offset in offsets {
+                ::hytale_protocol::__private::bytes::BufMut::put_i32_le(buf, offset);
+            }
+            ::hytale_protocol::__private::bytes::BufMut::put_slice(buf, &var_block);
+        }
+    });
+
+    let buf_param = if fields.is_empty() {
+        quote! { _buf }
+    } else {
+        quote! { buf }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::hytale_protocol::codec::PacketRead for #name #ty_generics #where_clause {
+            fn read(#buf_param: &mut ::hytale_protocol::codec::PacketBuffer) -> ::hytale_protocol::codec::CodecResult<Self> {
+                #read_null_bits
+                #(#read_inline)*
+                #read_offsets
+                #(#read_var)*
+                #finish_var
+                Ok(#construct)
+            }
+        }
+
+        impl #impl_generics ::hytale_protocol::codec::PacketWrite for #name #ty_generics #where_clause {
+            fn write(&self, #buf_param: &mut ::hytale_protocol::__private::bytes::BytesMut) {
+                #write_header
+                #(#write_inline)*
+                #write_offsets
+            }
+        }
+    })
+}
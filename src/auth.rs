@@ -0,0 +1,404 @@
+//! JWTs exchanged during the auth handshake.
+//!
+//! `AuthToken.access_token`, `AuthGrant.server_identity_token`,
+//! `AuthGrant.authorization_grant` and `ServerAuthToken.server_access_token`
+//! are signed JWTs. `TokenVerifier` checks them against a `KeySet`, usually
+//! the JWKS published by the session service; `Jwks` holds one in memory or
+//! loads it from a file, so tests can sign tokens with locally generated keys.
+
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{DecodingKey, Header, Validation};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use uuid::Uuid;
+
+pub use jsonwebtoken::Algorithm;
+pub use jsonwebtoken::jwk::{Jwk, JwkSet};
+
+/// Why a token was rejected or a key set couldn't be loaded.
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Invalid token: {0}")]
+    InvalidToken(#[from] jsonwebtoken::errors::Error),
+    #[error("No key to verify the token, key id {0:?}")]
+    UnknownKey(Option<String>),
+    #[error("Invalid JWKS: {0}")]
+    InvalidJwks(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+pub type AuthResult<T> = Result<T, AuthError>;
+
+/// Signature algorithms accepted by `TokenVerifier`. HMAC is left out, a public
+/// key must never be usable as a shared secret.
+const ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::EdDSA,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+];
+
+/// Claims of a token. Registered claims are typed, the others kept in `extra`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    /// Either a single string or an array on the wire.
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub aud: Vec<String>,
+    /// Expiry, in seconds since the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Claims {
+    /// UUID of the player or server the token was issued to.
+    pub fn subject(&self) -> Option<Uuid> {
+        self.sub
+            .as_deref()
+            .and_then(|sub| Uuid::parse_str(sub).ok())
+    }
+
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.exp.map(|exp| UNIX_EPOCH + Duration::from_secs(exp))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at()
+            .is_some_and(|expires_at| expires_at <= SystemTime::now())
+    }
+
+    pub fn has_audience(&self, audience: &str) -> bool {
+        self.aud.iter().any(|aud| aud == audience)
+    }
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Audience {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<Audience>::deserialize(deserializer)? {
+        None => Vec::new(),
+        Some(Audience::One(aud)) => vec![aud],
+        Some(Audience::Many(aud)) => aud,
+    })
+}
+
+/// A decoded token.
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub header: Header,
+    pub claims: Claims,
+}
+
+/// Decode a token without checking its signature or expiry, e.g. to log who
+/// it claims to be. Use `TokenVerifier` before trusting it.
+pub fn parse_unverified(token: &str) -> AuthResult<Token> {
+    let header = jsonwebtoken::decode_header(token)?;
+    let mut validation = Validation::new(header.alg);
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation.validate_aud = false;
+    validation.required_spec_claims.clear();
+    let data = jsonwebtoken::decode(token, &DecodingKey::from_secret(&[]), &validation)?;
+    Ok(Token {
+        header: data.header,
+        claims: data.claims,
+    })
+}
+
+/// Public keys tokens are verified against.
+///
+/// Implement it to plug in a key set that is fetched and refreshed remotely.
+pub trait KeySet {
+    /// The key with ID `kid`, the key ID of the token being verified.
+    fn find(&self, kid: Option<&str>) -> Option<Jwk>;
+}
+
+/// A JSON Web Key Set held in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jwks {
+    set: JwkSet,
+}
+
+impl Jwks {
+    pub fn new(set: JwkSet) -> Self {
+        Self { set }
+    }
+
+    /// Parse a JWKS document, `{"keys": [...]}`.
+    pub fn from_json(json: &str) -> AuthResult<Self> {
+        Ok(Self::new(serde_json::from_str(json)?))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> AuthResult<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn with_key(mut self, key: Jwk) -> Self {
+        self.set.keys.push(key);
+        self
+    }
+
+    pub fn keys(&self) -> &[Jwk] {
+        &self.set.keys
+    }
+}
+
+impl KeySet for Jwks {
+    /// Tokens without a key ID are only matched when the set holds a single key.
+    fn find(&self, kid: Option<&str>) -> Option<Jwk> {
+        match (kid, self.set.keys.as_slice()) {
+            (Some(kid), _) => self.set.find(kid).cloned(),
+            (None, [key]) => Some(key.clone()),
+            (None, _) => None,
+        }
+    }
+}
+
+impl Default for Jwks {
+    fn default() -> Self {
+        Self::new(JwkSet { keys: Vec::new() })
+    }
+}
+
+impl From<JwkSet> for Jwks {
+    fn from(set: JwkSet) -> Self {
+        Self::new(set)
+    }
+}
+
+/// Verifies the signature and the time, audience and issuer claims of tokens.
+///
+/// Tokens must carry an `exp` claim.
+pub struct TokenVerifier<K> {
+    keys: K,
+    audiences: Vec<String>,
+    issuers: Vec<String>,
+    leeway: Duration,
+}
+
+impl<K: KeySet> TokenVerifier<K> {
+    pub fn new(keys: K) -> Self {
+        Self {
+            keys,
+            audiences: Vec::new(),
+            issuers: Vec::new(),
+            leeway: Duration::from_secs(60),
+        }
+    }
+
+    /// Require the token to be issued to `audience`, or another audience added this way.
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audiences.push(audience.into());
+        self
+    }
+
+    /// Require the token to be issued by `issuer`, or another issuer added this way.
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuers.push(issuer.into());
+        self
+    }
+
+    /// Clock skew tolerated on `exp` and `nbf`, 60 seconds by default.
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    pub fn keys(&self) -> &K {
+        &self.keys
+    }
+
+    pub fn verify(&self, token: &str) -> AuthResult<Token> {
+        let header = jsonwebtoken::decode_header(token)?;
+        if !ALGORITHMS.contains(&header.alg) {
+            return Err(jsonwebtoken::errors::Error::from(ErrorKind::InvalidAlgorithm).into());
+        }
+        let key = self
+            .keys
+            .find(header.kid.as_deref())
+            .ok_or_else(|| AuthError::UnknownKey(header.kid.clone()))?;
+        // A key pinned to an algorithm can't be used with another one
+        if let Some(algorithm) = key.common.key_algorithm
+            && algorithm.to_string().parse::<Algorithm>().ok() != Some(header.alg)
+        {
+            return Err(jsonwebtoken::errors::Error::from(ErrorKind::InvalidAlgorithm).into());
+        }
+
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = self.leeway.as_secs();
+        validation.validate_nbf = true;
+        if self.audiences.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&self.audiences);
+            validation.required_spec_claims.insert("aud".to_string());
+        }
+        if !self.issuers.is_empty() {
+            validation.set_issuer(&self.issuers);
+            validation.required_spec_claims.insert("iss".to_string());
+        }
+
+        let data = jsonwebtoken::decode(token, &DecodingKey::from_jwk(&key)?, &validation)?;
+        Ok(Token {
+            header: data.header,
+            claims: data.claims,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use jsonwebtoken::EncodingKey;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use serde_json::json;
+
+    /// A freshly generated Ed25519 key, published under `kid` pinned to `alg`.
+    struct SigningKey {
+        pkcs8: Vec<u8>,
+        kid: &'static str,
+        jwks: Jwks,
+    }
+
+    impl SigningKey {
+        fn new(kid: &'static str, alg: &str) -> Self {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+            let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+            let x = URL_SAFE_NO_PAD.encode(pair.public_key().as_ref());
+            let jwks = Jwks::from_json(&format!(
+                r#"{{"keys":[{{"kty":"OKP","crv":"Ed25519","x":"{x}","kid":"{kid}","alg":"{alg}"}}]}}"#
+            ))
+            .unwrap();
+            Self {
+                pkcs8: pkcs8.as_ref().to_vec(),
+                kid,
+                jwks,
+            }
+        }
+
+        fn sign(&self, kid: &str, claims: serde_json::Value) -> String {
+            let mut header = Header::new(Algorithm::EdDSA);
+            header.kid = Some(kid.to_string());
+            jsonwebtoken::encode(&header, &claims, &EncodingKey::from_ed_der(&self.pkcs8)).unwrap()
+        }
+
+        fn verifier(&self) -> TokenVerifier<Jwks> {
+            TokenVerifier::new(self.jwks.clone())
+                .with_audience("hytale-server")
+                .with_issuer("https://sessions.example.com")
+        }
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn claims(aud: &str, exp: u64) -> serde_json::Value {
+        json!({
+            "iss": "https://sessions.example.com",
+            "sub": "00000000-0000-0000-0000-000000000042",
+            "aud": aud,
+            "exp": exp,
+            "username": "Player",
+            "scope": "hytale:client",
+        })
+    }
+
+    fn invalid_token(result: AuthResult<Token>) -> ErrorKind {
+        match result {
+            Err(AuthError::InvalidToken(error)) => error.into_kind(),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_signature() {
+        let key = SigningKey::new("key-1", "EdDSA");
+        let token = key.sign(key.kid, claims("hytale-server", now() + 600));
+        let token = key.verifier().verify(&token).unwrap();
+        assert_eq!(token.header.kid.as_deref(), Some("key-1"));
+        assert_eq!(token.claims.subject(), Some(Uuid::from_u128(0x42)));
+        assert_eq!(token.claims.username.as_deref(), Some("Player"));
+        assert!(token.claims.has_audience("hytale-server"));
+        assert!(!token.claims.is_expired());
+        assert_eq!(token.claims.extra["scope"], "hytale:client");
+    }
+
+    #[test]
+    fn expired_token() {
+        let key = SigningKey::new("key-1", "EdDSA");
+        let token = key.sign(key.kid, claims("hytale-server", now() - 3600));
+        assert_eq!(
+            invalid_token(key.verifier().verify(&token)),
+            ErrorKind::ExpiredSignature
+        );
+    }
+
+    #[test]
+    fn wrong_audience() {
+        let key = SigningKey::new("key-1", "EdDSA");
+        let token = key.sign(key.kid, claims("another-server", now() + 600));
+        assert_eq!(
+            invalid_token(key.verifier().verify(&token)),
+            ErrorKind::InvalidAudience
+        );
+    }
+
+    #[test]
+    fn unknown_kid() {
+        let key = SigningKey::new("key-1", "EdDSA");
+        let token = key.sign("key-2", claims("hytale-server", now() + 600));
+        assert!(matches!(
+            key.verifier().verify(&token),
+            Err(AuthError::UnknownKey(Some(kid))) if kid == "key-2"
+        ));
+    }
+
+    #[test]
+    fn algorithm_mismatch_with_a_pinned_key() {
+        // The key is published for ES256 only, an EdDSA signature must not pass
+        let key = SigningKey::new("key-1", "ES256");
+        let token = key.sign(key.kid, claims("hytale-server", now() + 600));
+        assert_eq!(
+            invalid_token(key.verifier().verify(&token)),
+            ErrorKind::InvalidAlgorithm
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Pretty-prints the packets of raw frame streams, captures and pcap files.
+//!
+//! Usage: `hytale-dump [--json] [--strict] [--direction server|client] [--port <port>]
+//! [--filter <name or id>]... <file or ->`
+//!
+//! `--json` needs the `serde` feature, which serializes the packet fields.
+
+use bytes::Bytes;
+use hytale_protocol::capture::{CaptureReader, CapturedPacket, MAGIC};
+use hytale_protocol::codec::{CodecError, DecodeOptions, FrameReader};
+use hytale_protocol::pcap::{self, PcapImporter};
+use hytale_protocol::{
+    ClientBoundPacket, ConnectionState, PacketDirection, PacketRegistry, ServerBoundPacket,
+    deserialize_client_bound_sized, deserialize_server_bound_sized,
+};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "Usage: hytale-dump [--json] [--strict] [--direction server|client] \
+                     [--port <port>] [--filter <name or id>]... <file or ->";
+
+struct Args {
+    path: String,
+    json: bool,
+    strict: bool,
+    /// Direction of a raw frame stream, captures record it per frame.
+    direction: Option<PacketDirection>,
+    port: u16,
+    filters: Vec<String>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Self {
+            path: String::new(),
+            json: false,
+            strict: false,
+            direction: None,
+            port: pcap::DEFAULT_PORT,
+            filters: Vec::new(),
+        };
+        let mut path = None;
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            let mut value = || iter.next().ok_or_else(|| format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--json" if cfg!(feature = "serde") => args.json = true,
+                "--json" => {
+                    return Err("--json needs hytale-dump built with the serde feature".to_string());
+                }
+                "--strict" => args.strict = true,
+                "--direction" => {
+                    args.direction = Some(match value()?.as_str() {
+                        "server" => PacketDirection::Serverbound,
+                        "client" => PacketDirection::Clientbound,
+                        other => return Err(format!("Unknown direction {other}")),
+                    })
+                }
+                "--port" => {
+                    let port = value()?;
+                    args.port = port.parse().map_err(|_| format!("Invalid port {port}"))?;
+                }
+                "--filter" => args.filters.extend(
+                    value()?
+                        .split(',')
+                        .filter(|filter| !filter.is_empty())
+                        .map(str::to_string),
+                ),
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {arg}")),
+                _ if path.is_none() => path = Some(arg),
+                _ => return Err(USAGE.to_string()),
+            }
+        }
+        args.path = path.ok_or_else(|| USAGE.to_string())?;
+        Ok(args)
+    }
+
+    fn decode_options(&self) -> DecodeOptions {
+        DecodeOptions {
+            strict: self.strict,
+            ..DecodeOptions::default()
+        }
+    }
+
+    /// Whether a packet matches one of the `--filter`s, by name or ID.
+    fn matches(&self, name: &str, id: u32) -> bool {
+        self.filters.is_empty()
+            || self
+                .filters
+                .iter()
+                .any(|filter| filter.eq_ignore_ascii_case(name) || filter.parse::<u32>() == Ok(id))
+    }
+}
+
+/// A decoded packet and what it looked like on the wire.
+struct Entry {
+    timestamp: Option<Duration>,
+    direction: PacketDirection,
+    /// Payload as it was on the wire.
+    payload: Bytes,
+    /// Size of the payload once decompressed, as the decoder saw it.
+    decompressed_size: usize,
+    packet: CapturedPacket,
+}
+
+impl Entry {
+    fn id(&self) -> u32 {
+        match &self.packet {
+            CapturedPacket::ServerBound(packet) => packet.id(),
+            CapturedPacket::ClientBound(packet) => packet.id(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match &self.packet {
+            CapturedPacket::ServerBound(packet) => packet.name(),
+            CapturedPacket::ClientBound(packet) => packet.name(),
+        }
+    }
+}
+
+struct Printer<W: Write> {
+    out: W,
+    args: Args,
+    registry: PacketRegistry,
+}
+
+impl<W: Write> Printer<W> {
+    fn print(&mut self, entry: Entry) -> Result<(), String> {
+        output(self.write_entry(entry))
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        output(self.out.flush())
+    }
+
+    fn write_entry(&mut self, entry: Entry) -> io::Result<()> {
+        let (id, name) = (entry.id(), entry.name());
+        // By name rather than ID, some IDs are shared by two packets
+        let info = self
+            .registry
+            .iter()
+            .find(|info| info.name == name && info.direction.overlaps(entry.direction));
+        if !self.args.matches(name, id) {
+            return Ok(());
+        }
+
+        let compressed = match &entry.packet {
+            CapturedPacket::ServerBound(ServerBoundPacket::Unknown { decompressed, .. })
+            | CapturedPacket::ClientBound(ClientBoundPacket::Unknown { decompressed, .. }) => {
+                *decompressed
+            }
+            _ => info.is_some_and(|info| info.compressed),
+        };
+        let decompressed_size = compressed.then_some(entry.decompressed_size);
+        if self.args.json {
+            let timestamp = entry
+                .timestamp
+                .map_or("null".to_string(), |t| format!("{:.6}", t.as_secs_f64()));
+            let direction = match entry.direction {
+                PacketDirection::Serverbound => "server_bound",
+                _ => "client_bound",
+            };
+            let decompressed_size = decompressed_size.map_or("null".to_string(), |s| s.to_string());
+            writeln!(
+                self.out,
+                "{{\"timestamp\":{timestamp},\"direction\":\"{direction}\",\"id\":{id},\
+                 \"name\":{},\"size\":{},\"decompressed_size\":{decompressed_size},\
+                 \"fields\":{}}}",
+                json_string(name),
+                entry.payload.len(),
+                json_fields(&entry.packet)?,
+            )
+        } else {
+            if let Some(timestamp) = entry.timestamp {
+                write!(self.out, "[{:.6}] ", timestamp.as_secs_f64())?;
+            }
+            let arrow = match entry.direction {
+                PacketDirection::Serverbound => "->",
+                _ => "<-",
+            };
+            write!(
+                self.out,
+                "{arrow} {name} ({id}), {} bytes",
+                entry.payload.len()
+            )?;
+            if let Some(size) = decompressed_size {
+                write!(self.out, " ({size} decompressed)")?;
+            }
+            writeln!(self.out, ": {}", debug_fields(&entry.packet))
+        }
+    }
+}
+
+fn output(result: io::Result<()>) -> Result<(), String> {
+    match result {
+        // Piped into `head` or the like, which has seen enough
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+        result => result.map_err(|e| e.to_string()),
+    }
+}
+
+/// Fields of a packet as `Name { .. }`.
+fn debug_fields(packet: &CapturedPacket) -> String {
+    let (name, debug) = match packet {
+        CapturedPacket::ServerBound(packet) => (packet.name(), format!("{packet:?}")),
+        CapturedPacket::ClientBound(packet) => (packet.name(), format!("{packet:?}")),
+    };
+    // `Name(Name { .. })` reads better as `Name { .. }`
+    debug
+        .strip_prefix(name)
+        .and_then(|fields| fields.strip_prefix('('))
+        .and_then(|fields| fields.strip_suffix(')'))
+        .map_or_else(|| debug.clone(), str::to_string)
+}
+
+/// Fields of a packet as a JSON object.
+#[cfg(feature = "serde")]
+fn json_fields(packet: &CapturedPacket) -> io::Result<String> {
+    let value = match packet {
+        CapturedPacket::ServerBound(packet) => serde_json::to_value(packet),
+        CapturedPacket::ClientBound(packet) => serde_json::to_value(packet),
+    }?;
+    // Variants are serialized as `{"Name": {..}}`, keep the inner object
+    let fields = match value {
+        serde_json::Value::Object(variant) if variant.len() == 1 => {
+            variant.into_iter().next().map(|(_, fields)| fields)
+        }
+        _ => None,
+    };
+    Ok(serde_json::to_string(&fields.unwrap_or_default())?)
+}
+
+/// `--json` is rejected by `Args::parse` without the `serde` feature.
+#[cfg(not(feature = "serde"))]
+fn json_fields(_: &CapturedPacket) -> io::Result<String> {
+    unreachable!("--json without the serde feature")
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Decode errors are reported and skipped, frames are delimited so the next one still decodes.
+fn report(error: &CodecError) {
+    eprintln!("error: {error}");
+}
+
+fn dump_capture<W: Write>(input: impl BufRead, printer: &mut Printer<W>) -> Result<(), String> {
+    let mut reader = CaptureReader::new(input).map_err(|e| e.to_string())?;
+    let options = printer.args.decode_options();
+    let mut warnings = Vec::new();
+    // Both directions are recorded, follow the phase like the peers did
+    let mut state = ConnectionState::default();
+    loop {
+        let frame = match reader.read_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return Ok(()),
+            Err(CodecError::Io(e)) => return Err(e.to_string()),
+            Err(e) => {
+                report(&e);
+                continue;
+            }
+        };
+        // Frames read from a capture always have a header
+        let (packet_id, payload) = (
+            frame.packet_id().unwrap_or_default(),
+            frame.payload().unwrap_or_default(),
+        );
+        let decoded = match frame.direction {
+            PacketDirection::Serverbound => state
+                .decode_server_bound_sized(packet_id, payload.clone(), options, &mut warnings)
+                .map(|(packet, size)| (CapturedPacket::ServerBound(packet), size)),
+            _ => state
+                .decode_client_bound_sized(packet_id, payload.clone(), options, &mut warnings)
+                .map(|(packet, size)| (CapturedPacket::ClientBound(packet), size)),
+        };
+        for warning in warnings.drain(..) {
+            eprintln!("warning: {warning}");
+        }
+        match decoded {
+            Ok((packet, decompressed_size)) => printer.print(Entry {
+                timestamp: Some(frame.timestamp),
+                direction: frame.direction,
+                payload,
+                decompressed_size,
+                packet,
+            })?,
+            Err(e) => report(&e),
+        }
+    }
+}
+
+fn dump_pcap<W: Write>(input: impl BufRead, printer: &mut Printer<W>) -> Result<(), String> {
+    let traffic = PcapImporter::new()
+        .with_server_port(printer.args.port)
+        .with_decode_options(printer.args.decode_options())
+        .import(input)
+        .map_err(|e| e.to_string())?;
+    for warning in &traffic.warnings {
+        eprintln!("warning: {warning}");
+    }
+    // The importer already skipped what follows in those connections
+    for skipped in &traffic.errors {
+        eprint!("{} -> {}: ", skipped.client, skipped.server);
+        report(&skipped.error);
+    }
+    // Relative to the first packet like captures, rather than since the epoch
+    let start = traffic.packets.first().map(|packet| packet.timestamp);
+    for packet in traffic.packets {
+        let entry = Entry {
+            timestamp: start.map(|start| packet.timestamp.saturating_sub(start)),
+            direction: packet.direction,
+            payload: packet.payload,
+            decompressed_size: packet.decompressed_size,
+            packet: packet.packet,
+        };
+        printer.print(entry)?;
+    }
+    Ok(())
+}
+
+fn dump_frames<W: Write>(input: impl BufRead, printer: &mut Printer<W>) -> Result<(), String> {
+    let direction = printer
+        .args
+        .direction
+        .ok_or("Raw frame streams need --direction server|client")?;
+    let options = printer.args.decode_options();
+    let mut reader = FrameReader::new(input, direction);
+    let mut warnings = Vec::new();
+    // Followed from one direction only, just enough to tell apart the packets
+    // sharing an ID. The server only speaks once the client sent Connect.
+    let mut state = match direction {
+        PacketDirection::Serverbound => ConnectionState::Handshake,
+        _ => ConnectionState::Auth,
+    };
+    while let Some((packet_id, payload)) = reader.read_raw_frame().map_err(|e| e.to_string())? {
+        let decoded = match direction {
+            PacketDirection::Serverbound => {
+                deserialize_server_bound_sized(packet_id, payload.clone(), options, &mut warnings)
+                    .map(|(packet, size)| (CapturedPacket::ServerBound(packet), size))
+            }
+            _ => deserialize_client_bound_sized(
+                state,
+                packet_id,
+                payload.clone(),
+                options,
+                &mut warnings,
+            )
+            .map(|(packet, size)| (CapturedPacket::ClientBound(packet), size)),
+        };
+        state.observe(direction, packet_id);
+        for warning in warnings.drain(..) {
+            eprintln!("warning: {warning}");
+        }
+        match decoded {
+            Ok((packet, decompressed_size)) => printer.print(Entry {
+                timestamp: None,
+                direction,
+                payload,
+                decompressed_size,
+                packet,
+            })?,
+            Err(e) => report(&e),
+        }
+    }
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let args = Args::parse()?;
+    let mut input: Box<dyn BufRead> = match args.path.as_str() {
+        "-" => Box::new(io::stdin().lock()),
+        path => Box::new(BufReader::new(
+            File::open(path).map_err(|e| format!("{path}: {e}"))?,
+        )),
+    };
+    // Enough to tell the formats apart, pipes may hand it out a byte at a time
+    let mut header = Vec::with_capacity(4);
+    while header.len() < 4 {
+        let buffered = input.fill_buf().map_err(|e| e.to_string())?;
+        if buffered.is_empty() {
+            break;
+        }
+        let taken = buffered.len().min(4 - header.len());
+        header.extend_from_slice(&buffered[..taken]);
+        input.consume(taken);
+    }
+    let input = io::Cursor::new(header.clone()).chain(input);
+
+    let mut printer = Printer {
+        out: BufWriter::new(io::stdout().lock()),
+        args,
+        registry: PacketRegistry::new(),
+    };
+    let result = if header.starts_with(&MAGIC) {
+        dump_capture(input, &mut printer)
+    } else if pcap::is_pcap(&header) {
+        dump_pcap(input, &mut printer)
+    } else {
+        dump_frames(input, &mut printer)
+    };
+    result.and(printer.flush())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Logs every packet going between Hytale clients and a server.
+//!
+//! Usage: `hytale-proxy [--strict] <listen address> <upstream address>`
+
+use hytale_protocol::codec::{CodecError, DecodeOptions, DecodeWarning};
+use hytale_protocol::proxy::{PacketFilter, Proxy, ProxyConnection};
+use hytale_protocol::{ClientBoundPacket, PacketDirection, ServerBoundPacket};
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::process::ExitCode;
+
+struct Logger;
+
+impl Logger {
+    fn log(&self, connection: &ProxyConnection, direction: PacketDirection, name: &str, id: u32) {
+        let arrow = match direction {
+            PacketDirection::Serverbound => "->",
+            _ => "<-",
+        };
+        eprintln!("[{}] {arrow} {name} ({id})", connection.id);
+    }
+}
+
+impl PacketFilter for Logger {
+    fn server_bound(
+        &self,
+        connection: &ProxyConnection,
+        packet: ServerBoundPacket,
+    ) -> Option<ServerBoundPacket> {
+        self.log(
+            connection,
+            PacketDirection::Serverbound,
+            packet.name(),
+            packet.id(),
+        );
+        Some(packet)
+    }
+
+    fn client_bound(
+        &self,
+        connection: &ProxyConnection,
+        packet: ClientBoundPacket,
+    ) -> Option<ClientBoundPacket> {
+        self.log(
+            connection,
+            PacketDirection::Clientbound,
+            packet.name(),
+            packet.id(),
+        );
+        Some(packet)
+    }
+
+    fn warning(&self, connection: &ProxyConnection, warning: DecodeWarning) {
+        eprintln!("[{}] warning: {warning}", connection.id);
+    }
+
+    fn undecodable(
+        &self,
+        connection: &ProxyConnection,
+        direction: PacketDirection,
+        packet_id: u32,
+        error: &CodecError,
+    ) {
+        self.log(connection, direction, "undecodable", packet_id);
+        eprintln!("[{}] {error}", connection.id);
+    }
+
+    fn accept_failed(&self, error: &io::Error) {
+        eprintln!("accept: {error}");
+    }
+
+    fn disconnected(&self, connection: &ProxyConnection, error: Option<&CodecError>) {
+        match error {
+            Some(error) => eprintln!(
+                "[{}] {} disconnected: {error}",
+                connection.id, connection.client
+            ),
+            None => eprintln!("[{}] {} disconnected", connection.id, connection.client),
+        }
+    }
+}
+
+fn resolve(address: &str) -> Result<SocketAddr, String> {
+    address
+        .to_socket_addrs()
+        .map_err(|e| format!("{address}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("{address}: no address found"))
+}
+
+fn run() -> Result<(), String> {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let strict = args.iter().any(|arg| arg == "--strict");
+    args.retain(|arg| arg != "--strict");
+    let [listen, upstream] = args.as_slice() else {
+        return Err(
+            "Usage: hytale-proxy [--strict] <listen address> <upstream address>".to_string(),
+        );
+    };
+
+    let upstream = resolve(upstream)?;
+    let listener = TcpListener::bind(resolve(listen)?).map_err(|e| format!("{listen}: {e}"))?;
+    eprintln!("Proxying {listen} to {upstream}");
+
+    let options = DecodeOptions {
+        strict,
+        ..DecodeOptions::default()
+    };
+    Proxy::new(upstream, Logger)
+        .with_decode_options(options)
+        .serve(listener)
+        .map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
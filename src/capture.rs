@@ -0,0 +1,658 @@
+//! Recording of timestamped frames to disk, and replaying them.
+//!
+//! A capture starts with `MAGIC` and a version byte, followed by records of
+//! `timestamp (u64 LE, microseconds since the capture started)`,
+//! `direction (u8, 0 server bound / 1 client bound)` and the frame exactly as
+//! it was on the wire (Length + ID + Payload, see `encode_raw_packet`).
+
+use crate::client_bound::ClientBoundPacket;
+use crate::codec::{
+    CodecError, CodecResult, DEFAULT_MAX_FRAME_LENGTH, DecodeOptions, DecodeWarning,
+    FRAME_HEADER_SIZE, Packet, encode_packet,
+};
+use crate::packet::PacketDirection;
+use crate::server_bound::ServerBoundPacket;
+use crate::session::{ClientSession, ServerSession};
+use crate::state::ConnectionState;
+use bytes::{BufMut, Bytes, BytesMut};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// First bytes of every capture.
+pub const MAGIC: [u8; 4] = *b"HYCP";
+
+/// Version of the capture format written by `CaptureWriter`.
+pub const CAPTURE_VERSION: u8 = 1;
+
+/// Size of a record header: timestamp + direction.
+const RECORD_HEADER_SIZE: usize = 9;
+
+/// A frame read back from a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedFrame {
+    /// Time since the capture started.
+    pub timestamp: Duration,
+    pub direction: PacketDirection,
+    /// The complete frame, Length + ID + Payload.
+    pub frame: Bytes,
+}
+
+impl CapturedFrame {
+    /// ID from the frame header, `None` if `frame` is shorter than a header.
+    pub fn packet_id(&self) -> Option<u32> {
+        let id = self.frame.get(4..FRAME_HEADER_SIZE)?;
+        Some(u32::from_le_bytes(id.try_into().ok()?))
+    }
+
+    /// Payload as it was on the wire, still compressed for compressed packets.
+    /// `None` if `frame` is shorter than a header.
+    pub fn payload(&self) -> Option<Bytes> {
+        (self.frame.len() >= FRAME_HEADER_SIZE).then(|| self.frame.slice(FRAME_HEADER_SIZE..))
+    }
+}
+
+/// A captured packet decoded with the packet enums.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum CapturedPacket {
+    ServerBound(ServerBoundPacket),
+    ClientBound(ClientBoundPacket),
+}
+
+fn direction_byte(direction: PacketDirection) -> CodecResult<u8> {
+    match direction {
+        PacketDirection::Serverbound => Ok(0),
+        PacketDirection::Clientbound => Ok(1),
+        PacketDirection::Bidirectional => Err(CodecError::Other(
+            "Captured frames travel in a single direction".to_string(),
+        )),
+    }
+}
+
+/// Records frames into a capture.
+pub struct CaptureWriter<W: Write> {
+    inner: W,
+    started: Instant,
+}
+
+impl CaptureWriter<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>) -> CodecResult<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Start a capture, writing its header. Timestamps are relative to now.
+    pub fn new(mut inner: W) -> CodecResult<Self> {
+        inner.write_all(&MAGIC)?;
+        inner.write_all(&[CAPTURE_VERSION])?;
+        Ok(Self {
+            inner,
+            started: Instant::now(),
+        })
+    }
+
+    /// Record a complete frame, timestamped with the time since the capture started.
+    pub fn record(&mut self, direction: PacketDirection, frame: &[u8]) -> CodecResult<()> {
+        self.record_at(self.started.elapsed(), direction, frame)
+    }
+
+    /// Record a complete frame with an explicit timestamp.
+    pub fn record_at(
+        &mut self,
+        timestamp: Duration,
+        direction: PacketDirection,
+        frame: &[u8],
+    ) -> CodecResult<()> {
+        if frame.len() < FRAME_HEADER_SIZE
+            || u32::from_le_bytes([frame[0], frame[1], frame[2], frame[3]]) as usize
+                != frame.len() - FRAME_HEADER_SIZE
+        {
+            return Err(CodecError::Other(
+                "Captured frames must be complete, see encode_raw_packet".to_string(),
+            ));
+        }
+
+        let mut header = BytesMut::with_capacity(RECORD_HEADER_SIZE);
+        header.put_u64_le(timestamp.as_micros() as u64);
+        header.put_u8(direction_byte(direction)?);
+        self.inner.write_all(&header)?;
+        self.inner.write_all(frame)?;
+        Ok(())
+    }
+
+    /// Encode `packet` and record it.
+    pub fn record_packet<P: Packet>(&mut self, packet: &P) -> CodecResult<()> {
+        let direction = match P::DIRECTION {
+            // Sent by both sides, we can't tell which one this is
+            PacketDirection::Bidirectional => {
+                return Err(CodecError::Other(
+                    "Use record with the direction of bidirectional packets".to_string(),
+                ));
+            }
+            direction => direction,
+        };
+        self.record(direction, &encode_packet(packet)?)
+    }
+
+    pub fn flush(&mut self) -> CodecResult<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads the frames of a capture back.
+pub struct CaptureReader<R: Read> {
+    inner: R,
+    max_frame_length: usize,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> CodecResult<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Open a capture, checking its header.
+    pub fn new(mut inner: R) -> CodecResult<Self> {
+        let mut header = [0u8; 5];
+        inner.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(CodecError::Other("Not a capture".to_string()));
+        }
+        if header[4] != CAPTURE_VERSION {
+            return Err(CodecError::Other(format!(
+                "Unsupported capture version {}",
+                header[4]
+            )));
+        }
+        Ok(Self {
+            inner,
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+        })
+    }
+
+    /// Reject frames whose payload is longer than `max_frame_length` bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    /// Read the next frame, `None` at the end of the capture.
+    ///
+    /// A record with an invalid direction or a frame longer than the maximum
+    /// is consumed before its error is returned, reading can go on after it.
+    pub fn read_frame(&mut self) -> CodecResult<Option<CapturedFrame>> {
+        let mut header = [0u8; RECORD_HEADER_SIZE + FRAME_HEADER_SIZE];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.inner.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let timestamp = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let length = u32::from_le_bytes(header[9..13].try_into().unwrap()) as usize;
+        // Records we reject are skipped, so that the next call reads the next record
+        let direction = match header[8] {
+            0 => PacketDirection::Serverbound,
+            1 => PacketDirection::Clientbound,
+            other => {
+                self.skip(length)?;
+                return Err(CodecError::InvalidEnumVariant(other as i32));
+            }
+        };
+        if length > self.max_frame_length {
+            self.skip(length)?;
+            return Err(CodecError::FrameTooLarge {
+                length,
+                max: self.max_frame_length,
+            });
+        }
+
+        let mut frame = vec![0u8; FRAME_HEADER_SIZE + length];
+        frame[..FRAME_HEADER_SIZE].copy_from_slice(&header[RECORD_HEADER_SIZE..]);
+        self.inner.read_exact(&mut frame[FRAME_HEADER_SIZE..])?;
+        Ok(Some(CapturedFrame {
+            timestamp: Duration::from_micros(timestamp),
+            direction,
+            frame: frame.into(),
+        }))
+    }
+
+    /// Discard the `length` bytes of payload of a rejected record.
+    fn skip(&mut self, length: usize) -> CodecResult<()> {
+        let skipped = io::copy(&mut (&mut self.inner).take(length as u64), &mut io::sink())?;
+        if skipped < length as u64 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = CodecResult<CapturedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+/// Plays a capture back, deterministically and as fast as it can be read.
+///
+/// Either decode it with `next_packet`, tracking the connection state the way
+/// both peers did, or feed one side of it into a session with `feed_server` /
+/// `feed_client` to reproduce what the session did with it. In the latter case
+/// the session only moves through the phases if the code under test answers
+/// like the original peer did (e.g. sends `WorldSettings` after `Connect`).
+pub struct Replayer<R: Read> {
+    reader: CaptureReader<R>,
+    state: ConnectionState,
+    decode_options: DecodeOptions,
+    warnings: Vec<DecodeWarning>,
+}
+
+impl<R: Read> Replayer<R> {
+    pub fn new(reader: CaptureReader<R>) -> Self {
+        Self {
+            reader,
+            state: ConnectionState::default(),
+            decode_options: DecodeOptions::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Decode packets strictly, or leniently while recording warnings.
+    pub fn with_decode_options(mut self, options: DecodeOptions) -> Self {
+        self.decode_options = options;
+        self
+    }
+
+    /// State of the replayed connection after the packets decoded so far.
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Decode the next frame, in either direction.
+    pub fn next_packet(&mut self) -> CodecResult<Option<(CapturedFrame, CapturedPacket)>> {
+        let Some(frame) = self.reader.read_frame()? else {
+            return Ok(None);
+        };
+        let (Some(packet_id), Some(payload)) = (frame.packet_id(), frame.payload()) else {
+            return Err(CodecError::BufferUnderflow {
+                expected: FRAME_HEADER_SIZE,
+                available: frame.frame.len(),
+            });
+        };
+        let packet = match frame.direction {
+            PacketDirection::Serverbound => {
+                CapturedPacket::ServerBound(self.state.decode_server_bound_with(
+                    packet_id,
+                    payload,
+                    self.decode_options,
+                    &mut self.warnings,
+                )?)
+            }
+            _ => CapturedPacket::ClientBound(self.state.decode_client_bound_with(
+                packet_id,
+                payload,
+                self.decode_options,
+                &mut self.warnings,
+            )?),
+        };
+        Ok(Some((frame, packet)))
+    }
+
+    /// Feed the next server bound frame to `session`, skipping what the server sent.
+    /// Returns the frame fed, `None` at the end of the capture.
+    pub fn feed_server(
+        &mut self,
+        session: &mut ServerSession,
+    ) -> CodecResult<Option<CapturedFrame>> {
+        let Some(frame) = self.next_frame(PacketDirection::Serverbound)? else {
+            return Ok(None);
+        };
+        session.receive(&frame.frame)?;
+        Ok(Some(frame))
+    }
+
+    /// Feed the next client bound frame to `session`, skipping what the client sent.
+    /// Returns the frame fed, `None` at the end of the capture.
+    pub fn feed_client(
+        &mut self,
+        session: &mut ClientSession,
+    ) -> CodecResult<Option<CapturedFrame>> {
+        let Some(frame) = self.next_frame(PacketDirection::Clientbound)? else {
+            return Ok(None);
+        };
+        session.receive(&frame.frame)?;
+        Ok(Some(frame))
+    }
+
+    fn next_frame(&mut self, direction: PacketDirection) -> CodecResult<Option<CapturedFrame>> {
+        while let Some(frame) = self.reader.read_frame()? {
+            if frame.direction == direction {
+                return Ok(Some(frame));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Warnings recorded while decoding leniently since the last call.
+    pub fn take_warnings(&mut self) -> Vec<DecodeWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::encode_raw_packet;
+    use crate::packets::auth::{AuthGrant, AuthToken};
+    use crate::packets::connection::{ClientType, Connect, PROTOCOL_HASH_LENGTH, Ping};
+    use crate::packets::player::{ClientReady, JoinWorld};
+    use crate::packets::setup::{RequestAssets, WorldLoadProgress, WorldSettings};
+    use crate::session::ServerEvent;
+    use uuid::Uuid;
+
+    fn connect() -> Connect {
+        Connect {
+            protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Game,
+            language: None,
+            identity_token: None,
+            uuid: Uuid::from_u128(1),
+            username: "Player".to_string(),
+            referral_data: None,
+            referral_source: None,
+        }
+    }
+
+    fn settings() -> WorldSettings {
+        WorldSettings {
+            game_version: "2026.01.13".to_string(),
+            world_seed: 1,
+            time_of_day: 0.5,
+            weather_seed: 2,
+            world_height: 320,
+            required_assets: None,
+        }
+    }
+
+    fn join() -> JoinWorld {
+        JoinWorld {
+            clear_world: true,
+            fade_in_out: true,
+            world_uuid: Uuid::from_u128(2),
+        }
+    }
+
+    /// A login as both peers sent it, in the order they did.
+    fn login() -> Vec<u8> {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer.record_packet(&connect()).unwrap();
+        let grant = AuthGrant {
+            authorization_grant: Some("grant".to_string()),
+            server_identity_token: None,
+        };
+        writer.record_packet(&grant).unwrap();
+        let token = AuthToken {
+            access_token: Some("access".to_string()),
+            server_authorization_grant: None,
+        };
+        writer.record_packet(&token).unwrap();
+        writer.record_packet(&settings()).unwrap();
+        let request = RequestAssets {
+            assets: Some(Vec::new()),
+        };
+        writer.record_packet(&request).unwrap();
+        // Shares ID 21 with WorldSettings
+        let progress = WorldLoadProgress {
+            percent_complete: 100,
+            percent_complete_subitem: 0,
+            status: None,
+        };
+        writer.record_packet(&progress).unwrap();
+        writer.record_packet(&join()).unwrap();
+        writer.record_packet(&ClientReady).unwrap();
+        writer.record_packet(&Ping { time: 7 }).unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn frame_header() {
+        let frame = |bytes: &'static [u8]| CapturedFrame {
+            timestamp: Duration::ZERO,
+            direction: PacketDirection::Serverbound,
+            frame: Bytes::from_static(bytes),
+        };
+        let full = frame(&[2, 0, 0, 0, 7, 0, 0, 0, 1, 2]);
+        assert_eq!(full.packet_id(), Some(7));
+        assert_eq!(full.payload().as_deref(), Some(&[1, 2][..]));
+        let short = frame(&[2, 0, 0, 0, 7]);
+        assert_eq!(short.packet_id(), None);
+        assert_eq!(short.payload(), None);
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let ping = encode_packet(&Ping { time: 7 }).unwrap();
+        let disconnect = encode_raw_packet(1, false, &[1, 2]).unwrap();
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer
+            .record_at(
+                Duration::from_millis(5),
+                PacketDirection::Serverbound,
+                &ping,
+            )
+            .unwrap();
+        writer
+            .record_at(
+                Duration::from_secs(2),
+                PacketDirection::Clientbound,
+                &disconnect,
+            )
+            .unwrap();
+        // Incomplete frames and frames without a direction are refused
+        assert!(
+            writer
+                .record(PacketDirection::Serverbound, &ping[..6])
+                .is_err()
+        );
+        assert!(
+            writer
+                .record(PacketDirection::Bidirectional, &ping)
+                .is_err()
+        );
+        let capture = writer.into_inner();
+
+        let frames = CaptureReader::new(&capture[..])
+            .unwrap()
+            .collect::<CodecResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            frames,
+            [
+                CapturedFrame {
+                    timestamp: Duration::from_millis(5),
+                    direction: PacketDirection::Serverbound,
+                    frame: ping.freeze(),
+                },
+                CapturedFrame {
+                    timestamp: Duration::from_secs(2),
+                    direction: PacketDirection::Clientbound,
+                    frame: disconnect,
+                },
+            ]
+        );
+        assert!(CaptureReader::new(&b"HYCP\x02"[..]).is_err());
+        assert!(CaptureReader::new(&b"PCAP\x01"[..]).is_err());
+    }
+
+    #[test]
+    fn rejected_records_are_skipped() {
+        let large = encode_raw_packet(9_999, false, &[0; 100]).unwrap();
+        let small = encode_raw_packet(9_999, false, &[0; 10]).unwrap();
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for frame in [&large, &small, &small, &small] {
+            writer
+                .record_at(Duration::ZERO, PacketDirection::Serverbound, frame)
+                .unwrap();
+        }
+        let mut capture = writer.into_inner();
+        // Direction byte of the third record
+        let third = MAGIC.len() + 1 + 2 * RECORD_HEADER_SIZE + large.len() + small.len();
+        capture[third + 8] = 7;
+
+        let mut reader = CaptureReader::new(&capture[..])
+            .unwrap()
+            .with_max_frame_length(50);
+        assert!(matches!(
+            reader.read_frame(),
+            Err(CodecError::FrameTooLarge {
+                length: 100,
+                max: 50
+            })
+        ));
+        assert_eq!(reader.read_frame().unwrap().unwrap().frame, small);
+        assert!(matches!(
+            reader.read_frame(),
+            Err(CodecError::InvalidEnumVariant(7))
+        ));
+        assert_eq!(reader.read_frame().unwrap().unwrap().frame, small);
+        assert!(reader.read_frame().unwrap().is_none());
+
+        // A rejected record cut short is an error, not the end of the capture
+        let mut reader = CaptureReader::new(&capture[..MAGIC.len() + 1 + RECORD_HEADER_SIZE + 20])
+            .unwrap()
+            .with_max_frame_length(50);
+        assert!(matches!(reader.read_frame(), Err(CodecError::Io(_))));
+    }
+
+    #[test]
+    fn replay_decodes_both_directions() {
+        let capture = login();
+        let mut replayer = Replayer::new(CaptureReader::new(&capture[..]).unwrap())
+            .with_decode_options(DecodeOptions::STRICT);
+        let mut seen = Vec::new();
+        while let Some((frame, packet)) = replayer.next_packet().unwrap() {
+            let name = match packet {
+                CapturedPacket::ServerBound(packet) => packet.name(),
+                CapturedPacket::ClientBound(packet) => packet.name(),
+            };
+            assert_eq!(
+                frame.payload().unwrap().len() + FRAME_HEADER_SIZE,
+                frame.frame.len()
+            );
+            seen.push((name, replayer.state()));
+        }
+        use ConnectionState::*;
+        assert_eq!(
+            seen,
+            [
+                ("Connect", Auth),
+                ("AuthGrant", Auth),
+                ("AuthToken", Auth),
+                ("WorldSettings", Setup),
+                ("RequestAssets", Setup),
+                ("WorldLoadProgress", Setup),
+                ("JoinWorld", Play),
+                ("ClientReady", Play),
+                ("Ping", Play),
+            ]
+        );
+        assert!(replayer.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn replay_into_a_server_session() {
+        let capture = login();
+        let mut replayer = Replayer::new(CaptureReader::new(&capture[..]).unwrap());
+        let mut server = ServerSession::new();
+
+        let frame = replayer.feed_server(&mut server).unwrap().unwrap();
+        assert_eq!(frame.packet_id(), Some(Connect::PACKET_ID));
+        assert!(matches!(
+            server.poll_event(),
+            Some(ServerEvent::PlayerConnected(connect)) if connect == self::connect()
+        ));
+        // Answered like the original server did, the session follows the login
+        replayer.feed_server(&mut server).unwrap().unwrap();
+        assert!(matches!(server.poll_event(), Some(ServerEvent::Packet(_))));
+        server.send(&settings()).unwrap();
+        replayer.feed_server(&mut server).unwrap().unwrap();
+        assert!(matches!(
+            server.poll_event(),
+            Some(ServerEvent::AssetsRequested(assets)) if assets.is_empty()
+        ));
+        server.send(&join()).unwrap();
+        replayer.feed_server(&mut server).unwrap().unwrap();
+        assert!(matches!(server.poll_event(), Some(ServerEvent::Ready)));
+        replayer.feed_server(&mut server).unwrap().unwrap();
+        assert_eq!(server.state(), ConnectionState::Play);
+        assert!(replayer.feed_server(&mut server).unwrap().is_none());
+
+        // Without the answers the session stays out of phase
+        let mut replayer = Replayer::new(CaptureReader::new(&capture[..]).unwrap());
+        let mut server = ServerSession::new();
+        replayer.feed_server(&mut server).unwrap();
+        replayer.feed_server(&mut server).unwrap();
+        assert!(matches!(
+            replayer.feed_server(&mut server),
+            Err(CodecError::ProtocolViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn replay_into_a_client_session() {
+        let capture = login();
+        let mut replayer = Replayer::new(CaptureReader::new(&capture[..]).unwrap());
+        let mut client = ClientSession::new(connect())
+            .unwrap()
+            .with_access_token("access");
+        assert!(client.poll_transmit().is_some());
+
+        let mut fed = Vec::new();
+        while let Some(frame) = replayer.feed_client(&mut client).unwrap() {
+            assert_eq!(frame.direction, PacketDirection::Clientbound);
+            fed.push(client.poll_packet().unwrap().name());
+        }
+        assert_eq!(
+            fed,
+            [
+                "AuthGrant",
+                "WorldSettings",
+                "WorldLoadProgress",
+                "JoinWorld"
+            ]
+        );
+        assert_eq!(client.state(), ConnectionState::Play);
+        // What the client answered, like the recorded client did
+        let answers: Vec<_> = std::iter::from_fn(|| client.poll_transmit())
+            .map(|frame| u32::from_le_bytes(frame[4..8].try_into().unwrap()))
+            .collect();
+        assert_eq!(
+            answers,
+            [
+                AuthToken::PACKET_ID,
+                RequestAssets::PACKET_ID,
+                ClientReady::PACKET_ID
+            ]
+        );
+    }
+}
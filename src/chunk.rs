@@ -84,7 +84,7 @@ impl ChunkSection {
 
         // Write block data as long array
         let longs_needed =
-            (CHUNK_SIZE * CHUNK_SIZE * SECTION_HEIGHT * bits_per_block as usize + 63) / 64;
+            (CHUNK_SIZE * CHUNK_SIZE * SECTION_HEIGHT * bits_per_block as usize).div_ceil(64);
         crate::codec::write_varint(buf, longs_needed as i32);
 
         // Pack blocks into longs
@@ -230,10 +230,8 @@ impl Chunk {
 
         // Write section data
         let mut section_data = BytesMut::new();
-        for section in &self.sections {
-            if let Some(s) = section {
-                s.write_to(&mut section_data);
-            }
+        for s in self.sections.iter().flatten() {
+            s.write_to(&mut section_data);
         }
 
         crate::codec::write_varint(buf, section_data.len() as i32);
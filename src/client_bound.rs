@@ -0,0 +1,291 @@
+use crate::codec::{
+    CodecError, CodecResult, DecodeOptions, DecodeWarning, Packet, decode_payload_sized,
+    encode_packet, unknown_payload,
+};
+use crate::compression::with_compressor;
+use crate::packet::PacketDirection;
+use crate::packets::auth::{AuthGrant, ServerAuthToken};
+use crate::packets::connection::{Disconnect, Pong};
+use crate::packets::entities::{ApplyKnockback, ChangeVelocity, EntityUpdates};
+use crate::packets::interface::{ChatMessage, ServerInfo};
+use crate::packets::player::JoinWorld;
+use crate::packets::setup::{
+    AssetFinalize, AssetInitialize, AssetPart, SetClientId, SetEntitySeed, SetTimeDilation,
+    SetUpdateRate, UpdateFeatures, ViewRadius, WorldLoadFinished, WorldLoadProgress,
+    WorldSettings,
+};
+use crate::packets::world::{PlaySoundEvent2D, PlaySoundEvent3D, ServerSetBlock, SetChunk};
+use crate::packets::worldmap::{UpdateWorldMap, UpdateWorldMapSettings};
+use crate::state::ConnectionState;
+use bytes::{Bytes, BytesMut};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClientBoundPacket {
+    Disconnect(Disconnect),
+    Pong(Pong),
+    AuthGrant(AuthGrant),
+    ServerAuthToken(ServerAuthToken),
+    WorldSettings(WorldSettings),
+    WorldLoadProgress(WorldLoadProgress),
+    WorldLoadFinished(WorldLoadFinished),
+    AssetInitialize(AssetInitialize),
+    AssetPart(AssetPart),
+    AssetFinalize(AssetFinalize),
+    SetUpdateRate(SetUpdateRate),
+    SetTimeDilation(SetTimeDilation),
+    UpdateFeatures(UpdateFeatures),
+    ViewRadius(ViewRadius),
+    ApplyKnockback(ApplyKnockback),
+    ChangeVelocity(ChangeVelocity),
+    ChatMessage(ChatMessage),
+    SetClientId(SetClientId),
+    JoinWorld(JoinWorld),
+    SetChunk(SetChunk),
+    ServerSetBlock(ServerSetBlock),
+    PlaySoundEvent2D(PlaySoundEvent2D),
+    PlaySoundEvent3D(PlaySoundEvent3D),
+    SetEntitySeed(SetEntitySeed),
+    EntityUpdates(EntityUpdates),
+    ServerInfo(ServerInfo),
+    UpdateWorldMapSettings(UpdateWorldMapSettings),
+    UpdateWorldMap(UpdateWorldMap),
+    /// A packet this crate doesn't model, kept so it can be logged or forwarded.
+    ///
+    /// `payload` is as it was on the wire, unless `decompressed` is set (see
+    /// `DecodeOptions::decompress_unknown`). `encode_raw_packet(id, decompressed, &payload)`
+    /// frames it again.
+    Unknown {
+        id: u32,
+        payload: Bytes,
+        decompressed: bool,
+    },
+}
+
+impl ClientBoundPacket {
+    /// ID of the packet on the wire.
+    pub fn id(&self) -> u32 {
+        match self {
+            ClientBoundPacket::Disconnect(_) => Disconnect::PACKET_ID,
+            ClientBoundPacket::Pong(_) => Pong::PACKET_ID,
+            ClientBoundPacket::AuthGrant(_) => AuthGrant::PACKET_ID,
+            ClientBoundPacket::ServerAuthToken(_) => ServerAuthToken::PACKET_ID,
+            ClientBoundPacket::WorldSettings(_) => WorldSettings::PACKET_ID,
+            ClientBoundPacket::WorldLoadProgress(_) => WorldLoadProgress::PACKET_ID,
+            ClientBoundPacket::WorldLoadFinished(_) => WorldLoadFinished::PACKET_ID,
+            ClientBoundPacket::AssetInitialize(_) => AssetInitialize::PACKET_ID,
+            ClientBoundPacket::AssetPart(_) => AssetPart::PACKET_ID,
+            ClientBoundPacket::AssetFinalize(_) => AssetFinalize::PACKET_ID,
+            ClientBoundPacket::SetUpdateRate(_) => SetUpdateRate::PACKET_ID,
+            ClientBoundPacket::SetTimeDilation(_) => SetTimeDilation::PACKET_ID,
+            ClientBoundPacket::UpdateFeatures(_) => UpdateFeatures::PACKET_ID,
+            ClientBoundPacket::ViewRadius(_) => ViewRadius::PACKET_ID,
+            ClientBoundPacket::ApplyKnockback(_) => ApplyKnockback::PACKET_ID,
+            ClientBoundPacket::ChangeVelocity(_) => ChangeVelocity::PACKET_ID,
+            ClientBoundPacket::ChatMessage(_) => ChatMessage::PACKET_ID,
+            ClientBoundPacket::SetClientId(_) => SetClientId::PACKET_ID,
+            ClientBoundPacket::JoinWorld(_) => JoinWorld::PACKET_ID,
+            ClientBoundPacket::SetChunk(_) => SetChunk::PACKET_ID,
+            ClientBoundPacket::ServerSetBlock(_) => ServerSetBlock::PACKET_ID,
+            ClientBoundPacket::PlaySoundEvent2D(_) => PlaySoundEvent2D::PACKET_ID,
+            ClientBoundPacket::PlaySoundEvent3D(_) => PlaySoundEvent3D::PACKET_ID,
+            ClientBoundPacket::SetEntitySeed(_) => SetEntitySeed::PACKET_ID,
+            ClientBoundPacket::EntityUpdates(_) => EntityUpdates::PACKET_ID,
+            ClientBoundPacket::ServerInfo(_) => ServerInfo::PACKET_ID,
+            ClientBoundPacket::UpdateWorldMapSettings(_) => UpdateWorldMapSettings::PACKET_ID,
+            ClientBoundPacket::UpdateWorldMap(_) => UpdateWorldMap::PACKET_ID,
+            ClientBoundPacket::Unknown { id, .. } => *id,
+        }
+    }
+
+    /// Name of the packet, as in `PacketInfo::name`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClientBoundPacket::Disconnect(_) => "Disconnect",
+            ClientBoundPacket::Pong(_) => "Pong",
+            ClientBoundPacket::AuthGrant(_) => "AuthGrant",
+            ClientBoundPacket::ServerAuthToken(_) => "ServerAuthToken",
+            ClientBoundPacket::WorldSettings(_) => "WorldSettings",
+            ClientBoundPacket::WorldLoadProgress(_) => "WorldLoadProgress",
+            ClientBoundPacket::WorldLoadFinished(_) => "WorldLoadFinished",
+            ClientBoundPacket::AssetInitialize(_) => "AssetInitialize",
+            ClientBoundPacket::AssetPart(_) => "AssetPart",
+            ClientBoundPacket::AssetFinalize(_) => "AssetFinalize",
+            ClientBoundPacket::SetUpdateRate(_) => "SetUpdateRate",
+            ClientBoundPacket::SetTimeDilation(_) => "SetTimeDilation",
+            ClientBoundPacket::UpdateFeatures(_) => "UpdateFeatures",
+            ClientBoundPacket::ViewRadius(_) => "ViewRadius",
+            ClientBoundPacket::ApplyKnockback(_) => "ApplyKnockback",
+            ClientBoundPacket::ChangeVelocity(_) => "ChangeVelocity",
+            ClientBoundPacket::ChatMessage(_) => "ChatMessage",
+            ClientBoundPacket::SetClientId(_) => "SetClientId",
+            ClientBoundPacket::JoinWorld(_) => "JoinWorld",
+            ClientBoundPacket::SetChunk(_) => "SetChunk",
+            ClientBoundPacket::ServerSetBlock(_) => "ServerSetBlock",
+            ClientBoundPacket::PlaySoundEvent2D(_) => "PlaySoundEvent2D",
+            ClientBoundPacket::PlaySoundEvent3D(_) => "PlaySoundEvent3D",
+            ClientBoundPacket::SetEntitySeed(_) => "SetEntitySeed",
+            ClientBoundPacket::EntityUpdates(_) => "EntityUpdates",
+            ClientBoundPacket::ServerInfo(_) => "ServerInfo",
+            ClientBoundPacket::UpdateWorldMapSettings(_) => "UpdateWorldMapSettings",
+            ClientBoundPacket::UpdateWorldMap(_) => "UpdateWorldMap",
+            ClientBoundPacket::Unknown { .. } => "Unknown",
+        }
+    }
+
+    /// Encode the packet into a complete frame (Length + ID + Payload).
+    pub fn encode(&self) -> CodecResult<BytesMut> {
+        match self {
+            ClientBoundPacket::Disconnect(packet) => encode_packet(packet),
+            ClientBoundPacket::Pong(packet) => encode_packet(packet),
+            ClientBoundPacket::AuthGrant(packet) => encode_packet(packet),
+            ClientBoundPacket::ServerAuthToken(packet) => encode_packet(packet),
+            ClientBoundPacket::WorldSettings(packet) => encode_packet(packet),
+            ClientBoundPacket::WorldLoadProgress(packet) => encode_packet(packet),
+            ClientBoundPacket::WorldLoadFinished(packet) => encode_packet(packet),
+            ClientBoundPacket::AssetInitialize(packet) => encode_packet(packet),
+            ClientBoundPacket::AssetPart(packet) => encode_packet(packet),
+            ClientBoundPacket::AssetFinalize(packet) => encode_packet(packet),
+            ClientBoundPacket::SetUpdateRate(packet) => encode_packet(packet),
+            ClientBoundPacket::SetTimeDilation(packet) => encode_packet(packet),
+            ClientBoundPacket::UpdateFeatures(packet) => encode_packet(packet),
+            ClientBoundPacket::ViewRadius(packet) => encode_packet(packet),
+            ClientBoundPacket::ApplyKnockback(packet) => encode_packet(packet),
+            ClientBoundPacket::ChangeVelocity(packet) => encode_packet(packet),
+            ClientBoundPacket::ChatMessage(packet) => encode_packet(packet),
+            ClientBoundPacket::SetClientId(packet) => encode_packet(packet),
+            ClientBoundPacket::JoinWorld(packet) => encode_packet(packet),
+            ClientBoundPacket::SetChunk(packet) => encode_packet(packet),
+            ClientBoundPacket::ServerSetBlock(packet) => encode_packet(packet),
+            ClientBoundPacket::PlaySoundEvent2D(packet) => encode_packet(packet),
+            ClientBoundPacket::PlaySoundEvent3D(packet) => encode_packet(packet),
+            ClientBoundPacket::SetEntitySeed(packet) => encode_packet(packet),
+            ClientBoundPacket::EntityUpdates(packet) => encode_packet(packet),
+            ClientBoundPacket::ServerInfo(packet) => encode_packet(packet),
+            ClientBoundPacket::UpdateWorldMapSettings(packet) => encode_packet(packet),
+            ClientBoundPacket::UpdateWorldMap(packet) => encode_packet(packet),
+            ClientBoundPacket::Unknown {
+                id,
+                payload,
+                decompressed,
+            } => with_compressor(|compressor| compressor.encode_frame(*id, *decompressed, payload)),
+        }
+    }
+}
+
+/// Deserialize a client bound packet based on its ID and payload.
+///
+/// The payload should be the raw packet data (excluding length and ID).
+/// This function handles decompression if the packet type requires it.
+///
+/// The IDs in `packet::SHARED_IDS` (21 is both WorldSettings and WorldLoadProgress)
+/// can't be told apart without knowing the phase of the connection, they fail
+/// with `CodecError::AmbiguousPacketId`. Use `deserialize_client_bound_in` or
+/// `ConnectionState::decode_client_bound` for those.
+pub fn deserialize_client_bound(packet_id: u32, payload: Bytes) -> CodecResult<ClientBoundPacket> {
+    deserialize_client_bound_with(packet_id, payload, DecodeOptions::default(), &mut Vec::new())
+}
+
+/// `deserialize_client_bound` with explicit options; lenient mode warnings are appended to `warnings`.
+pub fn deserialize_client_bound_with(
+    packet_id: u32,
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<ClientBoundPacket> {
+    decode(None, packet_id, payload, options, warnings).map(|(packet, _)| packet)
+}
+
+/// `deserialize_client_bound_with`, resolving shared IDs by the phase the connection is in.
+///
+/// Only shared IDs are checked against `state`, other packets decode in any
+/// phase and `state` is not advanced.
+pub fn deserialize_client_bound_in(
+    state: ConnectionState,
+    packet_id: u32,
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<ClientBoundPacket> {
+    deserialize_client_bound_sized(state, packet_id, payload, options, warnings)
+        .map(|(packet, _)| packet)
+}
+
+/// `deserialize_client_bound_in`, also returning the size of the payload once
+/// decompressed (its size on the wire if it isn't compressed).
+pub fn deserialize_client_bound_sized(
+    state: ConnectionState,
+    packet_id: u32,
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<(ClientBoundPacket, usize)> {
+    decode(Some(state), packet_id, payload, options, warnings)
+}
+
+/// Decode the payload of a `P` and wrap it in its variant.
+fn sized<P: Packet>(
+    variant: fn(P) -> ClientBoundPacket,
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<(ClientBoundPacket, usize)> {
+    decode_payload_sized(payload, options, warnings).map(|(packet, size)| (variant(packet), size))
+}
+
+/// Name of the packet a shared ID refers to in `state`.
+fn resolve_shared(state: Option<ConnectionState>, packet_id: u32) -> CodecResult<&'static str> {
+    let direction = PacketDirection::Clientbound;
+    match state {
+        Some(state) => Ok(state.get(direction, packet_id)?.name),
+        None => Err(CodecError::AmbiguousPacketId {
+            direction,
+            id: packet_id,
+        }),
+    }
+}
+
+fn decode(
+    state: Option<ConnectionState>,
+    packet_id: u32,
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<(ClientBoundPacket, usize)> {
+    match packet_id {
+        Disconnect::PACKET_ID => sized(ClientBoundPacket::Disconnect, payload, options, warnings),
+        Pong::PACKET_ID => sized(ClientBoundPacket::Pong, payload, options, warnings),
+        AuthGrant::PACKET_ID => sized(ClientBoundPacket::AuthGrant, payload, options, warnings),
+        ServerAuthToken::PACKET_ID => sized(ClientBoundPacket::ServerAuthToken, payload, options, warnings),
+        // WorldSettings shares its ID with WorldLoadProgress, the phase tells which one it is
+        WorldSettings::PACKET_ID if resolve_shared(state, packet_id)? == "WorldSettings" => sized(ClientBoundPacket::WorldSettings, payload, options, warnings),
+        WorldLoadProgress::PACKET_ID => sized(ClientBoundPacket::WorldLoadProgress, payload, options, warnings),
+        WorldLoadFinished::PACKET_ID => sized(ClientBoundPacket::WorldLoadFinished, payload, options, warnings),
+        AssetInitialize::PACKET_ID => sized(ClientBoundPacket::AssetInitialize, payload, options, warnings),
+        AssetPart::PACKET_ID => sized(ClientBoundPacket::AssetPart, payload, options, warnings),
+        AssetFinalize::PACKET_ID => sized(ClientBoundPacket::AssetFinalize, payload, options, warnings),
+        SetUpdateRate::PACKET_ID => sized(ClientBoundPacket::SetUpdateRate, payload, options, warnings),
+        SetTimeDilation::PACKET_ID => sized(ClientBoundPacket::SetTimeDilation, payload, options, warnings),
+        UpdateFeatures::PACKET_ID => sized(ClientBoundPacket::UpdateFeatures, payload, options, warnings),
+        ViewRadius::PACKET_ID => sized(ClientBoundPacket::ViewRadius, payload, options, warnings),
+        ApplyKnockback::PACKET_ID => sized(ClientBoundPacket::ApplyKnockback, payload, options, warnings),
+        ChangeVelocity::PACKET_ID => sized(ClientBoundPacket::ChangeVelocity, payload, options, warnings),
+        ChatMessage::PACKET_ID => sized(ClientBoundPacket::ChatMessage, payload, options, warnings),
+        SetClientId::PACKET_ID => sized(ClientBoundPacket::SetClientId, payload, options, warnings),
+        JoinWorld::PACKET_ID => sized(ClientBoundPacket::JoinWorld, payload, options, warnings),
+        SetChunk::PACKET_ID => sized(ClientBoundPacket::SetChunk, payload, options, warnings),
+        ServerSetBlock::PACKET_ID => sized(ClientBoundPacket::ServerSetBlock, payload, options, warnings),
+        PlaySoundEvent2D::PACKET_ID => sized(ClientBoundPacket::PlaySoundEvent2D, payload, options, warnings),
+        PlaySoundEvent3D::PACKET_ID => sized(ClientBoundPacket::PlaySoundEvent3D, payload, options, warnings),
+        SetEntitySeed::PACKET_ID => sized(ClientBoundPacket::SetEntitySeed, payload, options, warnings),
+        EntityUpdates::PACKET_ID => sized(ClientBoundPacket::EntityUpdates, payload, options, warnings),
+        ServerInfo::PACKET_ID => sized(ClientBoundPacket::ServerInfo, payload, options, warnings),
+        UpdateWorldMapSettings::PACKET_ID => sized(ClientBoundPacket::UpdateWorldMapSettings, payload, options, warnings),
+        UpdateWorldMap::PACKET_ID => sized(ClientBoundPacket::UpdateWorldMap, payload, options, warnings),
+        _ => {
+            let (payload, decompressed) = unknown_payload(payload, options);
+            let size = payload.len();
+            Ok((ClientBoundPacket::Unknown { id: packet_id, payload, decompressed }, size))
+        }
+    }
+}
@@ -1,5 +1,11 @@
+use crate::compression::{Compressor, Decompressor, with_compressor, with_decompressor};
+use crate::packet::{PacketDirection, PacketRegistry};
+use crate::state::ConnectionState;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::fmt;
+use std::io::{self, Read, Write};
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Debug, Error)]
 pub enum CodecError {
@@ -7,25 +13,205 @@ pub enum CodecError {
     BufferUnderflow { expected: usize, available: usize },
     #[error("Invalid string length: {0}")]
     InvalidStringLength(usize),
+    #[error("VarInt is longer than 5 bytes")]
+    VarIntTooLong,
+    #[error("Frame too large: {length} bytes, maximum is {max}")]
+    FrameTooLarge { length: usize, max: usize },
     #[error("Invalid enum variant: {0}")]
     InvalidEnumVariant(i32),
+    #[error("Invalid collection length {count} with {available} bytes left")]
+    InvalidCollectionLength { count: i32, available: usize },
+    #[error("Packet {packet_id} too large: {length} bytes, maximum is {max}")]
+    PacketTooLarge {
+        packet_id: u32,
+        length: usize,
+        max: usize,
+    },
+    #[error("{0} unexpected bytes after the packet")]
+    TrailingBytes(usize),
+    #[error("Invalid offset {offset} for variable field {slot}")]
+    InvalidOffset { slot: usize, offset: i32 },
+    #[error("Protocol violation: {direction:?} packet {id} is not allowed in state {state:?}")]
+    ProtocolViolation {
+        state: ConnectionState,
+        direction: PacketDirection,
+        id: u32,
+    },
+    #[error("{direction:?} packet {id} names several packets, decode it with the connection state")]
+    AmbiguousPacketId { direction: PacketDirection, id: u32 },
+    #[error("Protocol hash mismatch: expected {expected}, got {actual}")]
+    ProtocolHashMismatch { expected: String, actual: String },
     #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(#[from] io::Error),
     #[error("UTF-8 error: {0}")]
     Utf8(#[from] std::string::FromUtf8Error),
     #[error("Codec error: {0}")]
     Other(String),
+    #[error("{0}")]
+    Decode(Box<DecodeError>),
+}
+
+/// Where in a packet decoding failed.
+#[derive(Debug)]
+pub struct DecodeError {
+    /// Name and ID of the packet, once known.
+    pub packet: Option<(&'static str, u32)>,
+    /// Fields from the packet down to the one that failed, e.g. `["skin", "haircut"]`.
+    pub path: Vec<&'static str>,
+    /// Absolute position in the (decompressed) payload where reading stopped.
+    pub offset: usize,
+    pub source: CodecError,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.packet {
+            Some((name, id)) => write!(f, "Failed to decode {name} (id {id})")?,
+            None => write!(f, "Failed to decode")?,
+        }
+        if !self.path.is_empty() {
+            let name = self.packet.map_or("", |(name, _)| name);
+            write!(f, " at {name}.{}", self.path.join("."))?;
+        }
+        write!(f, ", byte {}: {}", self.offset, self.source)
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl CodecError {
+    /// Record that the error happened while reading `field`, `offset` being
+    /// where reading stopped. Called from the innermost field outwards.
+    pub fn in_field(self, field: &'static str, offset: usize) -> Self {
+        match self {
+            CodecError::Decode(mut error) => {
+                error.path.insert(0, field);
+                CodecError::Decode(error)
+            }
+            source => CodecError::Decode(Box::new(DecodeError {
+                packet: None,
+                path: vec![field],
+                offset,
+                source,
+            })),
+        }
+    }
+
+    /// Record the packet the error happened in.
+    pub fn in_packet(self, name: &'static str, id: u32, offset: usize) -> Self {
+        match self {
+            CodecError::Decode(mut error) => {
+                error.packet = Some((name, id));
+                CodecError::Decode(error)
+            }
+            source => CodecError::Decode(Box::new(DecodeError {
+                packet: Some((name, id)),
+                path: Vec::new(),
+                offset,
+                source,
+            })),
+        }
+    }
 }
 
 pub type CodecResult<T> = Result<T, CodecError>;
 
-/// Trait for packets with an ID.
-pub trait Packet {
+/// How strictly payloads are checked while decoding.
+///
+/// Lenient (the default) accepts unknown enum discriminants and bytes left
+/// after the packet, recording a `DecodeWarning` for each; strict fails on them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    pub strict: bool,
+    /// Decompress the payload of unknown packets when it looks zstd compressed,
+    /// instead of keeping it as it was on the wire.
+    pub decompress_unknown: bool,
+}
+
+impl DecodeOptions {
+    pub const STRICT: Self = Self {
+        strict: true,
+        decompress_unknown: false,
+    };
+    pub const LENIENT: Self = Self {
+        strict: false,
+        decompress_unknown: false,
+    };
+
+    pub const fn with_decompress_unknown(mut self, decompress_unknown: bool) -> Self {
+        self.decompress_unknown = decompress_unknown;
+        self
+    }
+}
+
+/// Something unexpected that lenient decoding let through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeWarning {
+    /// Name and ID of the packet, once known.
+    pub packet: Option<(&'static str, u32)>,
+    /// Absolute position in the (decompressed) payload.
+    pub offset: usize,
+    pub kind: DecodeWarningKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeWarningKind {
+    /// Unknown discriminant `value` of enum `name`, read as its fallback variant.
+    UnknownEnumVariant { name: &'static str, value: i32 },
+    /// Bytes left unread after the packet.
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((name, id)) = self.packet {
+            write!(f, "{name} (id {id}), ")?;
+        }
+        write!(f, "byte {}: ", self.offset)?;
+        match &self.kind {
+            DecodeWarningKind::UnknownEnumVariant { name, value } => {
+                write!(f, "unknown {name} variant {value}")
+            }
+            DecodeWarningKind::TrailingBytes(count) => {
+                write!(f, "{count} unexpected bytes after the packet")
+            }
+        }
+    }
+}
+
+/// Maximum length in bytes of a VarInt-prefixed string.
+pub const MAX_STRING_LENGTH: usize = 32767;
+
+/// Worst case encoded size of a VarInt-prefixed string.
+pub const MAX_VAR_STRING_SIZE: usize = 3 + MAX_STRING_LENGTH;
+
+/// Size of the frame header: Length (4 bytes LE) + ID (4 bytes LE).
+pub const FRAME_HEADER_SIZE: usize = 8;
+
+/// Default cap on the payload length announced by a frame header.
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Trait for Hytale packets.
+///
+/// This is the only packet trait of the crate, `crate::packet::Packet` re-exports it.
+pub trait Packet: PacketRead + PacketWrite + Send + Sync + 'static {
     const PACKET_ID: u32;
+    const DIRECTION: PacketDirection;
 
     /// Whether the packet payload should be Zstd compressed.
-    fn is_compressed() -> bool {
-        false
+    const IS_COMPRESSED: bool = false;
+
+    /// Maximum size of the (decompressed) payload in bytes.
+    const MAX_SIZE: usize;
+
+    /// Reject values `write` can't encode faithfully, checked before every
+    /// encode. Most packets have nothing to check.
+    fn validate(&self) -> CodecResult<()> {
+        Ok(())
     }
 }
 
@@ -39,20 +225,124 @@ pub trait PacketWrite {
     fn write(&self, buf: &mut BytesMut);
 }
 
+/// Generates a little-endian and a big-endian reader for a primitive type.
+macro_rules! read_primitive {
+    ($le:ident, $be:ident, $ty:ty, $get_le:ident, $get_be:ident) => {
+        pub fn $le(&mut self) -> CodecResult<$ty> {
+            self.require(std::mem::size_of::<$ty>())?;
+            Ok(self.data.$get_le())
+        }
+
+        pub fn $be(&mut self) -> CodecResult<$ty> {
+            self.require(std::mem::size_of::<$ty>())?;
+            Ok(self.data.$get_be())
+        }
+    };
+}
+
 /// Buffer wrapper for reading packet data.
 pub struct PacketBuffer {
     data: Bytes,
+    /// Absolute position of `data` in the payload, for error reporting.
+    base: usize,
+    len: usize,
+    options: DecodeOptions,
+    warnings: Vec<DecodeWarning>,
 }
 
 impl PacketBuffer {
     pub fn new(data: Bytes) -> Self {
-        Self { data }
+        Self::with_options(data, DecodeOptions::default())
+    }
+
+    pub fn with_options(data: Bytes, options: DecodeOptions) -> Self {
+        Self::at(data, 0, options)
+    }
+
+    /// Buffer over `data`, which starts at `base` in the payload.
+    fn at(data: Bytes, base: usize, options: DecodeOptions) -> Self {
+        Self {
+            len: data.len(),
+            data,
+            base,
+            options,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn options(&self) -> DecodeOptions {
+        self.options
+    }
+
+    /// Warnings recorded so far in lenient mode.
+    pub fn take_warnings(&mut self) -> Vec<DecodeWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn warn(&mut self, kind: DecodeWarningKind) {
+        self.warnings.push(DecodeWarning {
+            packet: None,
+            offset: self.position(),
+            kind,
+        });
+    }
+
+    /// Handle the unknown discriminant `value` of enum `name`: an error in
+    /// strict mode, `fallback` and a warning otherwise.
+    pub fn unknown_variant<T>(
+        &mut self,
+        name: &'static str,
+        value: i32,
+        fallback: T,
+    ) -> CodecResult<T> {
+        if self.options.strict {
+            return Err(CodecError::InvalidEnumVariant(value));
+        }
+        self.warn(DecodeWarningKind::UnknownEnumVariant { name, value });
+        Ok(fallback)
+    }
+
+    /// Check that the whole buffer was read: an error in strict mode, a warning otherwise.
+    pub fn finish(&mut self) -> CodecResult<()> {
+        let count = self.remaining();
+        if count == 0 {
+            return Ok(());
+        }
+        if self.options.strict {
+            return Err(CodecError::TrailingBytes(count));
+        }
+        self.warn(DecodeWarningKind::TrailingBytes(count));
+        Ok(())
     }
 
     pub fn remaining(&self) -> usize {
         self.data.remaining()
     }
 
+    /// Absolute position of the next byte to read in the payload.
+    pub fn position(&self) -> usize {
+        self.base + self.len - self.data.remaining()
+    }
+
+    /// Read a named field, adding its name and position to any error.
+    pub fn field<T>(
+        &mut self,
+        name: &'static str,
+        read: impl FnOnce(&mut Self) -> CodecResult<T>,
+    ) -> CodecResult<T> {
+        read(self).map_err(|e| e.in_field(name, self.position()))
+    }
+
+    fn require(&self, len: usize) -> CodecResult<()> {
+        if self.data.remaining() < len {
+            return Err(CodecError::BufferUnderflow {
+                expected: len,
+                available: self.data.remaining(),
+            });
+        }
+        Ok(())
+    }
+
     pub fn read_u8(&mut self) -> CodecResult<u8> {
         if self.data.remaining() < 1 {
             return Err(CodecError::BufferUnderflow {
@@ -71,76 +361,92 @@ impl PacketBuffer {
         Ok(self.read_u8()? as i8)
     }
 
+    pub fn read_i32_le(&mut self) -> CodecResult<i32> {
+        Ok(self.read_u32_le()? as i32)
+    }
+
+    pub fn read_int_le(&mut self) -> CodecResult<i32> {
+        self.read_i32_le()
+    }
+
+    read_primitive!(read_u16_le, read_u16_be, u16, get_u16_le, get_u16);
+    read_primitive!(read_u32_le, read_u32_be, u32, get_u32_le, get_u32);
+    read_primitive!(read_u64_le, read_u64_be, u64, get_u64_le, get_u64);
+    read_primitive!(read_f32_le, read_f32_be, f32, get_f32_le, get_f32);
+    read_primitive!(read_f64_le, read_f64_be, f64, get_f64_le, get_f64);
+
+    pub fn read_i16_le(&mut self) -> CodecResult<i16> {
+        Ok(self.read_u16_le()? as i16)
+    }
+
+    pub fn read_i16_be(&mut self) -> CodecResult<i16> {
+        Ok(self.read_u16_be()? as i16)
+    }
+
+    pub fn read_i32_be(&mut self) -> CodecResult<i32> {
+        Ok(self.read_u32_be()? as i32)
+    }
+
+    pub fn read_i64_le(&mut self) -> CodecResult<i64> {
+        Ok(self.read_u64_le()? as i64)
+    }
+
+    pub fn read_i64_be(&mut self) -> CodecResult<i64> {
+        Ok(self.read_u64_be()? as i64)
+    }
+
+    /// Read a UUID as two big-endian longs (most significant first), like Java.
+    pub fn read_uuid_be(&mut self) -> CodecResult<Uuid> {
+        let most = self.read_u64_be()? as u128;
+        let least = self.read_u64_be()? as u128;
+        Ok(Uuid::from_u128((most << 64) | least))
+    }
+
+    /// Read a UUID as two little-endian longs (most significant first).
+    pub fn read_uuid_le(&mut self) -> CodecResult<Uuid> {
+        let most = self.read_u64_le()? as u128;
+        let least = self.read_u64_le()? as u128;
+        Ok(Uuid::from_u128((most << 64) | least))
+    }
+
+    #[deprecated(note = "ambiguous endianness, use read_u16_le or read_u16_be")]
     pub fn read_u16(&mut self) -> CodecResult<u16> {
-        if self.data.remaining() < 2 {
-            return Err(CodecError::BufferUnderflow {
-                expected: 2,
-                available: self.data.remaining(),
-            });
-        }
-        Ok(self.data.get_u16())
+        self.read_u16_be()
     }
 
+    #[deprecated(note = "ambiguous endianness, use read_i16_le or read_i16_be")]
     pub fn read_i16(&mut self) -> CodecResult<i16> {
-        Ok(self.read_u16()? as i16)
+        self.read_i16_be()
     }
 
+    #[deprecated(note = "ambiguous endianness, use read_u32_le or read_u32_be")]
     pub fn read_u32(&mut self) -> CodecResult<u32> {
-        if self.data.remaining() < 4 {
-            return Err(CodecError::BufferUnderflow {
-                expected: 4,
-                available: self.data.remaining(),
-            });
-        }
-        Ok(self.data.get_u32())
+        self.read_u32_be()
     }
 
+    #[deprecated(note = "ambiguous endianness, use read_i32_le or read_i32_be")]
     pub fn read_i32(&mut self) -> CodecResult<i32> {
-        Ok(self.read_u32()? as i32)
-    }
-
-    pub fn read_int_le(&mut self) -> CodecResult<i32> {
-        if self.data.remaining() < 4 {
-            return Err(CodecError::BufferUnderflow {
-                expected: 4,
-                available: self.data.remaining(),
-            });
-        }
-        Ok(self.data.get_i32_le())
+        self.read_i32_be()
     }
 
+    #[deprecated(note = "ambiguous endianness, use read_u64_le or read_u64_be")]
     pub fn read_u64(&mut self) -> CodecResult<u64> {
-        if self.data.remaining() < 8 {
-            return Err(CodecError::BufferUnderflow {
-                expected: 8,
-                available: self.data.remaining(),
-            });
-        }
-        Ok(self.data.get_u64())
+        self.read_u64_be()
     }
 
+    #[deprecated(note = "ambiguous endianness, use read_i64_le or read_i64_be")]
     pub fn read_i64(&mut self) -> CodecResult<i64> {
-        Ok(self.read_u64()? as i64)
+        self.read_i64_be()
     }
 
+    #[deprecated(note = "ambiguous endianness, use read_f32_le or read_f32_be")]
     pub fn read_f32(&mut self) -> CodecResult<f32> {
-        if self.data.remaining() < 4 {
-            return Err(CodecError::BufferUnderflow {
-                expected: 4,
-                available: self.data.remaining(),
-            });
-        }
-        Ok(self.data.get_f32())
+        self.read_f32_be()
     }
 
+    #[deprecated(note = "ambiguous endianness, use read_f64_le or read_f64_be")]
     pub fn read_f64(&mut self) -> CodecResult<f64> {
-        if self.data.remaining() < 8 {
-            return Err(CodecError::BufferUnderflow {
-                expected: 8,
-                available: self.data.remaining(),
-            });
-        }
-        Ok(self.data.get_f64())
+        self.read_f64_be()
     }
 
     pub fn read_bool(&mut self) -> CodecResult<bool> {
@@ -162,7 +468,7 @@ impl PacketBuffer {
 
             position += 7;
             if position >= 32 {
-                return Err(CodecError::InvalidStringLength(position as usize));
+                return Err(CodecError::VarIntTooLong);
             }
         }
 
@@ -173,9 +479,23 @@ impl PacketBuffer {
         self.read_varint()
     }
 
+    /// Read the VarInt length of a collection whose elements take at least
+    /// `min_element_size` bytes, rejecting lengths the remaining bytes cannot hold.
+    ///
+    /// Use it before `Vec::with_capacity` so a hostile peer cannot make us allocate
+    /// more than the packet it sent.
+    pub fn read_count(&mut self, min_element_size: usize) -> CodecResult<usize> {
+        let count = self.read_varint()?;
+        let available = self.data.remaining();
+        match usize::try_from(count) {
+            Ok(len) if len.saturating_mul(min_element_size) <= available => Ok(len),
+            _ => Err(CodecError::InvalidCollectionLength { count, available }),
+        }
+    }
+
     pub fn read_string(&mut self) -> CodecResult<String> {
         let len = self.read_varint()? as usize;
-        if len > 32767 {
+        if len > MAX_STRING_LENGTH {
             return Err(CodecError::InvalidStringLength(len));
         }
         if self.data.remaining() < len {
@@ -203,6 +523,80 @@ impl PacketBuffer {
     }
 }
 
+/// Reads the variable block of a packet through its offset table.
+///
+/// The offsets are relative to the start of the variable block, which begins
+/// right after the table. Each field is read from its own offset, so the
+/// fields don't have to be laid out in order or contiguously.
+pub struct VarBlockReader {
+    offsets: Vec<i32>,
+    block: Bytes,
+    /// Absolute position of the variable block.
+    base: usize,
+    /// Byte ranges of the fields read so far.
+    ranges: Vec<(usize, usize)>,
+    options: DecodeOptions,
+    /// Warnings from the fields, handed back to the packet buffer by `finish`.
+    warnings: Vec<DecodeWarning>,
+}
+
+impl VarBlockReader {
+    /// Read an offset table of `slots` entries; the variable block starts right after it.
+    pub fn new(buf: &mut PacketBuffer, slots: usize) -> CodecResult<Self> {
+        let offsets = (0..slots)
+            .map(|_| buf.read_i32_le())
+            .collect::<CodecResult<Vec<_>>>()?;
+        Ok(Self {
+            offsets,
+            block: buf.data.clone(),
+            base: buf.position(),
+            ranges: Vec::with_capacity(slots),
+            options: buf.options,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Raw offset of `slot`, `-1` when the field is null.
+    pub fn offset(&self, slot: usize) -> i32 {
+        self.offsets[slot]
+    }
+
+    /// Read the field stored at the offset of `slot`.
+    ///
+    /// Fails with `CodecError::InvalidOffset` if the offset is out of bounds or
+    /// the field overlaps one that has already been read.
+    pub fn read_field<T>(
+        &mut self,
+        slot: usize,
+        read: impl FnOnce(&mut PacketBuffer) -> CodecResult<T>,
+    ) -> CodecResult<T> {
+        let offset = self.offsets[slot];
+        let start = match usize::try_from(offset) {
+            Ok(start) if start < self.block.len() => start,
+            _ => return Err(CodecError::InvalidOffset { slot, offset }),
+        };
+
+        let mut field =
+            PacketBuffer::at(self.block.slice(start..), self.base + start, self.options);
+        let value = read(&mut field)?;
+        self.warnings.append(&mut field.warnings);
+        let end = self.block.len() - field.remaining();
+
+        if self.ranges.iter().any(|&(s, e)| start < e && s < end) {
+            return Err(CodecError::InvalidOffset { slot, offset });
+        }
+        self.ranges.push((start, end));
+        Ok(value)
+    }
+
+    /// Advance `buf` past the last byte of the variable block that was read.
+    pub fn finish(mut self, buf: &mut PacketBuffer) {
+        let end = self.ranges.iter().map(|&(_, e)| e).max().unwrap_or(0);
+        buf.data.advance(end);
+        buf.warnings.append(&mut self.warnings);
+    }
+}
+
 // Write helpers
 pub fn write_varint(buf: &mut BytesMut, mut value: i32) {
     loop {
@@ -215,13 +609,18 @@ pub fn write_varint(buf: &mut BytesMut, mut value: i32) {
     }
 }
 
+/// Write a UUID as two big-endian longs (most significant first), like Java.
+pub fn write_uuid(buf: &mut BytesMut, uuid: &Uuid) {
+    buf.put_u128(uuid.as_u128());
+}
+
 pub fn write_string(buf: &mut BytesMut, s: &str) {
     write_varint(buf, s.len() as i32);
     buf.put_slice(s.as_bytes());
 }
 
 pub fn var_int_size(val: i32) -> i32 {
-    let mut x = val as u32;
+    let x = val as u32;
     if (x & 0xFFFFFF80) == 0 {
         1
     } else if (x & 0xFFFFC000) == 0 {
@@ -236,42 +635,123 @@ pub fn var_int_size(val: i32) -> i32 {
 }
 
 /// Encode a packet into a complete frame (Length + ID + Payload).
-pub fn encode_packet<P: Packet + PacketWrite>(packet: &P) -> CodecResult<BytesMut> {
-    let mut payload_buf = BytesMut::new();
-    packet.write(&mut payload_buf);
-
-    let payload = if P::is_compressed() && !payload_buf.is_empty() {
-        // Use bulk compress to ensure content size is written to the frame header,
-        // which matches Java's Zstd.compress behavior and expectation.
-        let compressed = zstd::bulk::compress(&payload_buf, 0).map_err(CodecError::Io)?;
-        BytesMut::from(&compressed[..])
+/// Compressed packets go through this thread's shared `Compressor`.
+pub fn encode_packet<P: Packet>(packet: &P) -> CodecResult<BytesMut> {
+    with_compressor(|compressor| compressor.encode_packet(packet))
+}
+
+/// Decode a packet payload (excluding Length and ID).
+/// Handles decompression if needed, up to `P::MAX_SIZE` bytes.
+/// Uncompressed payloads are read in place: blob fields are slices of `payload`.
+pub fn decode_payload<P: Packet>(payload: Bytes) -> CodecResult<P> {
+    decode_payload_with(payload, DecodeOptions::default(), &mut Vec::new())
+}
+
+/// `decode_payload` with explicit options; lenient mode warnings are appended to `warnings`.
+pub fn decode_payload_with<P: Packet>(
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<P> {
+    decode_payload_sized(payload, options, warnings).map(|(packet, _)| packet)
+}
+
+/// `decode_payload_with`, also returning the size of the payload once decompressed.
+pub(crate) fn decode_payload_sized<P: Packet>(
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<(P, usize)> {
+    check_payload_size(P::PACKET_ID, P::IS_COMPRESSED, payload.len(), P::MAX_SIZE)?;
+    let data: Bytes = if P::IS_COMPRESSED && !payload.is_empty() {
+        with_decompressor(|decompressor| decompressor.decompress(&payload, P::MAX_SIZE))
+            .map_err(|e| too_large(P::PACKET_ID, e))?
+            .into()
     } else {
-        payload_buf
+        payload
     };
 
-    let mut frame = BytesMut::with_capacity(8 + payload.len());
-    frame.put_u32_le(payload.len() as u32);
-    frame.put_u32_le(P::PACKET_ID);
-    frame.put(payload);
+    let size = data.len();
+    Ok((read_packet(data, options, warnings)?, size))
+}
 
-    Ok(frame)
+/// Read a packet from its uncompressed payload, naming it in errors and warnings.
+pub(crate) fn read_packet<P: Packet>(
+    data: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<P> {
+    let name = PacketRegistry::new()
+        .get(P::DIRECTION, P::PACKET_ID)
+        .map_or_else(std::any::type_name::<P>, |info| info.name);
+    let mut buf = PacketBuffer::with_options(data, options);
+    let packet = P::read(&mut buf)
+        .and_then(|packet| buf.finish().map(|()| packet))
+        .map_err(|e| e.in_packet(name, P::PACKET_ID, buf.position()))?;
+    warnings.extend(
+        buf.take_warnings()
+            .into_iter()
+            .map(|warning| DecodeWarning {
+                packet: Some((name, P::PACKET_ID)),
+                ..warning
+            }),
+    );
+    Ok(packet)
 }
 
-/// Decode a packet payload (excluding Length and ID).
-/// Handles decompression if needed.
-pub fn decode_payload<P: Packet + PacketRead>(payload: &[u8]) -> CodecResult<P> {
-    let data = if P::is_compressed() && !payload.is_empty() {
-        // Use bulk decompress to match the encoding.
-        // We use a generous limit (50MB) for decompression since we don't have per-packet MAX_SIZE in the trait yet.
-        // Hytale packets can be large (up to ~32MB for ServerInfo, larger for WorldSettings).
-        const DECOMPRESSION_LIMIT: usize = 50 * 1024 * 1024;
-        zstd::bulk::decompress(payload, DECOMPRESSION_LIMIT).map_err(CodecError::Io)?
+/// Magic number at the start of a zstd frame.
+pub(crate) const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Payload of a packet we don't model, and whether it was decompressed.
+///
+/// With `options.decompress_unknown`, payloads starting with a zstd frame are
+/// decompressed (up to `DEFAULT_MAX_FRAME_LENGTH`); anything that fails to
+/// decompress is kept as is, since the magic number may be a coincidence.
+pub(crate) fn unknown_payload(payload: Bytes, options: DecodeOptions) -> (Bytes, bool) {
+    if !options.decompress_unknown || !payload.starts_with(&ZSTD_MAGIC) {
+        return (payload, false);
+    }
+    match with_decompressor(|decompressor| {
+        decompressor.decompress(&payload, DEFAULT_MAX_FRAME_LENGTH)
+    }) {
+        Ok(data) => (data.into(), true),
+        Err(_) => (payload, false),
+    }
+}
+
+/// Reject a payload as it is on the wire if it can't fit in `max_size` once decompressed.
+pub(crate) fn check_payload_size(
+    packet_id: u32,
+    is_compressed: bool,
+    length: usize,
+    max_size: usize,
+) -> CodecResult<()> {
+    // Incompressible data grows a little when compressed
+    let max = if is_compressed {
+        zstd::zstd_safe::compress_bound(max_size)
     } else {
-        payload.to_vec()
+        max_size
     };
+    if length > max {
+        return Err(CodecError::PacketTooLarge {
+            packet_id,
+            length,
+            max,
+        });
+    }
+    Ok(())
+}
 
-    let mut buf = PacketBuffer::new(Bytes::from(data));
-    P::read(&mut buf)
+/// Attach the packet ID to a `FrameTooLarge` raised while decompressing.
+pub(crate) fn too_large(packet_id: u32, error: CodecError) -> CodecError {
+    match error {
+        CodecError::FrameTooLarge { length, max } => CodecError::PacketTooLarge {
+            packet_id,
+            length,
+            max,
+        },
+        error => error,
+    }
 }
 
 /// Encodes a raw packet from ID and payload.
@@ -282,25 +762,415 @@ pub fn encode_raw_packet(
     is_compressed: bool,
     payload: &[u8],
 ) -> CodecResult<Bytes> {
-    // Match the behavior of encode_packet exactly
-    let final_payload = if is_compressed && !payload.is_empty() {
-        // Use bulk compress with level 0 to ensure content size is written to the frame header,
-        // which matches Java's Zstd.compress behavior and expectation.
-        let compressed = zstd::bulk::compress(payload, 0).map_err(CodecError::Io)?;
-        BytesMut::from(&compressed[..])
-    } else {
-        BytesMut::from(payload)
-    };
+    with_compressor(|compressor| compressor.encode_frame(packet_id, is_compressed, payload))
+        .map(BytesMut::freeze)
+}
+
+/// Splits the next complete frame off the front of `src`.
+///
+/// Returns `Ok(None)` and leaves `src` untouched when more bytes are needed.
+pub fn split_frame(
+    src: &mut BytesMut,
+    max_frame_length: usize,
+) -> CodecResult<Option<(u32, Bytes)>> {
+    if src.len() < FRAME_HEADER_SIZE {
+        return Ok(None);
+    }
+
+    let length = u32::from_le_bytes([src[0], src[1], src[2], src[3]]) as usize;
+    if length > max_frame_length {
+        return Err(CodecError::FrameTooLarge {
+            length,
+            max: max_frame_length,
+        });
+    }
+
+    let frame_len = FRAME_HEADER_SIZE + length;
+    if src.len() < frame_len {
+        // Wait for the rest of the frame
+        src.reserve(frame_len - src.len());
+        return Ok(None);
+    }
 
-    // Now wrap in the frame structure: Length (4 bytes LE) + ID (4 bytes LE) + Data
-    let mut frame = BytesMut::with_capacity(8 + final_payload.len());
+    src.advance(4);
+    let packet_id = src.get_u32_le();
+    let payload = src.split_to(length).freeze();
+    Ok(Some((packet_id, payload)))
+}
+
+/// Reads complete frames from a blocking `std::io::Read`.
+pub struct FrameReader<R> {
+    inner: R,
+    direction: PacketDirection,
+    max_frame_length: usize,
+    /// Own decompressor, the thread's shared one otherwise.
+    decompressor: Option<Decompressor>,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Create a reader for frames travelling in `direction`.
+    /// The direction is used to look up which packets are compressed.
+    pub fn new(inner: R, direction: PacketDirection) -> Self {
+        Self {
+            inner,
+            direction,
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            decompressor: None,
+        }
+    }
+
+    /// Reject frames whose payload is longer than `max_frame_length` bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
 
-    // Write Length (4 bytes LE) - just the payload size
-    frame.put_u32_le(final_payload.len() as u32);
-    // Write ID (4 bytes LE)
-    frame.put_u32_le(packet_id);
-    // Write Payload
-    frame.put(final_payload);
+    /// Decompress with `decompressor`, e.g. one set up with a dictionary.
+    pub fn with_decompressor(mut self, decompressor: Decompressor) -> Self {
+        self.decompressor = Some(decompressor);
+        self
+    }
+
+    /// Read the next frame as it is on the wire (payload still compressed).
+    /// Returns `None` when the stream ends cleanly between two frames.
+    pub fn read_raw_frame(&mut self) -> CodecResult<Option<(u32, Bytes)>> {
+        let mut header = [0u8; FRAME_HEADER_SIZE];
+        let mut filled = 0;
+        while filled < FRAME_HEADER_SIZE {
+            match self.inner.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
 
-    Ok(frame.freeze())
+        let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let packet_id = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if length > self.max_frame_length {
+            return Err(CodecError::FrameTooLarge {
+                length,
+                max: self.max_frame_length,
+            });
+        }
+
+        let mut payload = vec![0u8; length];
+        self.inner.read_exact(&mut payload)?;
+        Ok(Some((packet_id, Bytes::from(payload))))
+    }
+
+    /// Read the next frame, decompressing its payload if the packet is compressed.
+    pub fn read_frame(&mut self) -> CodecResult<Option<(u32, Bytes)>> {
+        let Some((packet_id, payload)) = self.read_raw_frame()? else {
+            return Ok(None);
+        };
+
+        match PacketRegistry::new().get(self.direction, packet_id) {
+            Some(info) => {
+                check_payload_size(packet_id, info.compressed, payload.len(), info.max_size)?;
+                if !info.compressed || payload.is_empty() {
+                    return Ok(Some((packet_id, payload)));
+                }
+                let data = match &mut self.decompressor {
+                    Some(decompressor) => decompressor.decompress(&payload, info.max_size),
+                    None => with_decompressor(|decompressor| {
+                        decompressor.decompress(&payload, info.max_size)
+                    }),
+                }
+                .map_err(|e| too_large(packet_id, e))?;
+                Ok(Some((packet_id, Bytes::from(data))))
+            }
+            None => Ok(Some((packet_id, payload))),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Writes complete frames to a blocking `std::io::Write`.
+pub struct FrameWriter<W> {
+    inner: W,
+    /// Own compressor, the thread's shared one otherwise.
+    compressor: Option<Compressor>,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            compressor: None,
+        }
+    }
+
+    /// Compress with `compressor`, e.g. one with another level or a dictionary.
+    pub fn with_compressor(mut self, compressor: Compressor) -> Self {
+        self.compressor = Some(compressor);
+        self
+    }
+
+    /// Encode and write a packet, compressing it if needed.
+    pub fn write_packet<P: Packet>(&mut self, packet: &P) -> CodecResult<()> {
+        let frame = match &mut self.compressor {
+            Some(compressor) => compressor.encode_packet(packet),
+            None => with_compressor(|compressor| compressor.encode_packet(packet)),
+        }?;
+        self.inner.write_all(&frame)?;
+        Ok(())
+    }
+
+    /// Write a frame from an ID and an uncompressed payload, see `encode_raw_packet`.
+    pub fn write_frame(
+        &mut self,
+        packet_id: u32,
+        is_compressed: bool,
+        payload: &[u8],
+    ) -> CodecResult<()> {
+        let frame = match &mut self.compressor {
+            Some(compressor) => compressor.encode_frame(packet_id, is_compressed, payload),
+            None => with_compressor(|compressor| {
+                compressor.encode_frame(packet_id, is_compressed, payload)
+            }),
+        }?;
+        self.inner.write_all(&frame)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> CodecResult<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_bound::{ClientBoundPacket, deserialize_client_bound_with};
+    use crate::common::Asset;
+    use crate::packets::connection::{ClientType, Connect, PROTOCOL_HASH_LENGTH};
+    use crate::packets::interface::{ChatMessage, ChatType};
+    use crate::packets::setup::{AssetPart, WorldSettings};
+    use crate::packets::world::SetChunk;
+    use crate::server_bound::{ServerBoundPacket, deserialize_server_bound_with};
+
+    /// Hands out one byte per `read`, like a slow socket.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((&byte, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = byte;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    fn chat() -> ChatMessage {
+        ChatMessage {
+            message: "hello".to_string(),
+            chat_type: ChatType::Normal,
+            sender_name: Some("Player".to_string()),
+        }
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.write_packet(&chat()).unwrap();
+        writer.write_frame(0x7FFF_0000, false, &[1, 2, 3]).unwrap();
+        writer.flush().unwrap();
+        let data = writer.into_inner();
+
+        let mut reader = FrameReader::new(Trickle(&data), PacketDirection::Serverbound);
+        let (id, payload) = reader.read_frame().unwrap().unwrap();
+        assert_eq!(id, ChatMessage::PACKET_ID);
+        assert_eq!(decode_payload::<ChatMessage>(payload).unwrap(), chat());
+        let (id, payload) = reader.read_frame().unwrap().unwrap();
+        assert_eq!(id, 0x7FFF_0000);
+        assert_eq!(payload[..], [1, 2, 3]);
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn read_frame_decompresses() {
+        let settings = WorldSettings {
+            game_version: "2026.01.13".to_string(),
+            world_seed: 1,
+            time_of_day: 0.5,
+            weather_seed: 2,
+            world_height: 320,
+            required_assets: Some(vec![Asset {
+                hash: "0".repeat(64),
+                name: "Sky.png".to_string(),
+            }]),
+        };
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.write_packet(&settings).unwrap();
+        let data = writer.into_inner();
+
+        let mut reader = FrameReader::new(&data[..], PacketDirection::Clientbound);
+        let (_, raw) = reader.read_raw_frame().unwrap().unwrap();
+        assert!(raw.starts_with(&ZSTD_MAGIC));
+
+        let mut reader = FrameReader::new(&data[..], PacketDirection::Clientbound);
+        let (id, payload) = reader.read_frame().unwrap().unwrap();
+        assert_eq!(id, WorldSettings::PACKET_ID);
+        let mut expected = BytesMut::new();
+        settings.write(&mut expected);
+        assert_eq!(payload, expected);
+    }
+
+    #[test]
+    fn rejects_hostile_length_prefix() {
+        let mut header = u32::MAX.to_le_bytes().to_vec();
+        header.extend_from_slice(&ChatMessage::PACKET_ID.to_le_bytes());
+        let mut reader =
+            FrameReader::new(&header[..], PacketDirection::Serverbound).with_max_frame_length(1024);
+        assert!(matches!(
+            reader.read_frame(),
+            Err(CodecError::FrameTooLarge {
+                length,
+                max: 1024
+            }) if length == u32::MAX as usize
+        ));
+    }
+
+    #[test]
+    fn eof_inside_a_frame_is_an_error() {
+        let frame = encode_packet(&chat()).unwrap();
+        let mut reader = FrameReader::new(&frame[..5], PacketDirection::Serverbound);
+        assert!(matches!(
+            reader.read_frame(),
+            Err(CodecError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+        let mut reader = FrameReader::new(&frame[..frame.len() - 1], PacketDirection::Serverbound);
+        assert!(matches!(
+            reader.read_frame(),
+            Err(CodecError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn clean_eof_ends_the_stream() {
+        let mut reader = FrameReader::new(&[][..], PacketDirection::Serverbound);
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    /// Whether `slice` points into `buffer` rather than into a copy.
+    fn points_into(slice: &[u8], buffer: &[u8]) -> bool {
+        let (slice, buffer) = (slice.as_ptr_range(), buffer.as_ptr_range());
+        buffer.start <= slice.start && slice.end <= buffer.end
+    }
+
+    #[test]
+    fn blobs_are_slices_of_the_payload() {
+        // Uncompressed: straight from the frame
+        let frame = encode_packet(&Connect {
+            protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Game,
+            language: None,
+            identity_token: None,
+            uuid: Uuid::nil(),
+            username: "Player".to_string(),
+            referral_data: Some(Bytes::from_static(&[1, 2, 3])),
+            referral_source: None,
+        })
+        .unwrap();
+        let mut reader = FrameReader::new(&frame[..], PacketDirection::Serverbound);
+        let (_, payload) = reader.read_frame().unwrap().unwrap();
+        let connect: Connect = decode_payload(payload.clone()).unwrap();
+        let referral_data = connect.referral_data.unwrap();
+        assert_eq!(referral_data[..], [1, 2, 3]);
+        assert!(points_into(&referral_data, &payload));
+
+        // Compressed: from the buffer they were decompressed into
+        let data: Bytes = (0..10_000).map(|i| (i % 13) as u8).collect();
+        let mut writer = FrameWriter::new(Vec::new());
+        writer
+            .write_packet(&AssetPart {
+                part: Some(data.clone()),
+            })
+            .unwrap();
+        writer
+            .write_packet(&SetChunk {
+                x: 0,
+                y: 0,
+                z: 0,
+                local_light: None,
+                global_light: Some(data.slice(..64)),
+                data: Some(data.clone()),
+            })
+            .unwrap();
+        let frames = writer.into_inner();
+        let mut reader = FrameReader::new(&frames[..], PacketDirection::Clientbound);
+
+        let (_, payload) = reader.read_frame().unwrap().unwrap();
+        let part =
+            read_packet::<AssetPart>(payload.clone(), DecodeOptions::STRICT, &mut Vec::new())
+                .unwrap()
+                .part
+                .unwrap();
+        assert_eq!(part, data);
+        assert!(points_into(&part, &payload));
+
+        let (_, payload) = reader.read_frame().unwrap().unwrap();
+        let chunk =
+            read_packet::<SetChunk>(payload.clone(), DecodeOptions::STRICT, &mut Vec::new())
+                .unwrap();
+        assert!(points_into(chunk.global_light.as_ref().unwrap(), &payload));
+        assert!(points_into(chunk.data.as_ref().unwrap(), &payload));
+    }
+
+    #[test]
+    fn unknown_packets_re_encode_as_they_came() {
+        let data: Vec<u8> = (0..2_000).map(|i| (i % 7) as u8).collect();
+        let frames = [
+            encode_raw_packet(9_999, true, &data).unwrap(),
+            encode_raw_packet(9_999, false, &data).unwrap(),
+        ];
+        for frame in frames {
+            for decompress_unknown in [false, true] {
+                let options = DecodeOptions::STRICT.with_decompress_unknown(decompress_unknown);
+                let compressed = frame[8..].starts_with(&ZSTD_MAGIC);
+                let payload = Bytes::copy_from_slice(&frame[8..]);
+
+                let packet =
+                    deserialize_client_bound_with(9_999, payload.clone(), options, &mut Vec::new())
+                        .unwrap();
+                let ClientBoundPacket::Unknown { decompressed, .. } = &packet else {
+                    panic!("expected an unknown packet, got {packet:?}");
+                };
+                assert_eq!(*decompressed, compressed && decompress_unknown);
+                assert_eq!(packet.encode().unwrap()[..], frame[..]);
+
+                let packet =
+                    deserialize_server_bound_with(9_999, payload, options, &mut Vec::new())
+                        .unwrap();
+                let ServerBoundPacket::Unknown { decompressed, .. } = &packet else {
+                    panic!("expected an unknown packet, got {packet:?}");
+                };
+                assert_eq!(*decompressed, compressed && decompress_unknown);
+                assert_eq!(packet.encode().unwrap()[..], frame[..]);
+            }
+        }
+    }
 }
@@ -2,20 +2,27 @@ use crate::codec::{CodecResult, PacketBuffer, PacketRead, PacketWrite};
 use bytes::{BufMut, BytesMut};
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Asset {
     pub hash: String,
     pub name: String,
 }
 
+impl Asset {
+    /// Smallest encoding: the 64 byte hash and an empty name.
+    pub const MIN_SIZE: usize = 64 + 1;
+}
+
 impl PacketRead for Asset {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         // hash is fixed 64 bytes ASCII
-        let hash_bytes = buf.read_bytes(64)?;
-        let hash =
-            String::from_utf8(hash_bytes.to_vec()).map_err(crate::codec::CodecError::Utf8)?;
+        let hash = buf.field("hash", |buf| {
+            let hash_bytes = buf.read_bytes(64)?;
+            String::from_utf8(hash_bytes.to_vec()).map_err(crate::codec::CodecError::Utf8)
+        })?;
 
         // name is var string
-        let name = buf.read_var_string()?;
+        let name = buf.field("name", |buf| buf.read_var_string())?;
 
         Ok(Asset {
             hash: hash.trim_matches(char::from(0)).to_string(), // Trim null padding if any
@@ -0,0 +1,273 @@
+//! Zstd compression with contexts that are kept around between packets.
+//!
+//! `encode_packet`, `decode_payload` and `encode_raw_packet` go through a
+//! thread local `Compressor` / `Decompressor`, created on first use; hot paths
+//! that want their own level or a dictionary can own one instead.
+
+use crate::codec::{
+    CodecError, CodecResult, DecodeOptions, DecodeWarning, FRAME_HEADER_SIZE, Packet,
+    check_payload_size, read_packet, too_large,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use std::cell::RefCell;
+use std::io;
+use zstd::zstd_safe;
+
+/// Level used by the Java server (`Zstd.compress` default).
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 0;
+
+/// Reusable zstd compression context.
+pub struct Compressor {
+    ctx: zstd::bulk::Compressor<'static>,
+    /// Uncompressed payload of the packet being encoded.
+    payload: BytesMut,
+    /// Compressed output, reused between packets.
+    output: Vec<u8>,
+}
+
+impl Compressor {
+    pub fn new(level: i32) -> CodecResult<Self> {
+        let ctx = zstd::bulk::Compressor::new(level)?;
+        Ok(Self::from_context(ctx))
+    }
+
+    /// Compress with a dictionary, which pays off for many small similar
+    /// frames such as `SetChunk`. The peer must decompress with the same one.
+    pub fn with_dictionary(level: i32, dictionary: &[u8]) -> CodecResult<Self> {
+        let ctx = zstd::bulk::Compressor::with_dictionary(level, dictionary)?;
+        Ok(Self::from_context(ctx))
+    }
+
+    fn from_context(ctx: zstd::bulk::Compressor<'static>) -> Self {
+        Self {
+            ctx,
+            payload: BytesMut::new(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn set_level(&mut self, level: i32) -> CodecResult<()> {
+        self.ctx.set_compression_level(level)?;
+        Ok(())
+    }
+
+    /// Compress `data` into the internal buffer and return it.
+    ///
+    /// The content size is written to the zstd frame header, as Java's `Zstd.compress` does.
+    pub fn compress(&mut self, data: &[u8]) -> CodecResult<&[u8]> {
+        self.output.clear();
+        self.output.reserve(zstd_safe::compress_bound(data.len()));
+        self.ctx.compress_to_buffer(data, &mut self.output)?;
+        Ok(&self.output)
+    }
+
+    /// Encode a packet into a complete frame (Length + ID + Payload).
+    pub fn encode_packet<P: Packet>(&mut self, packet: &P) -> CodecResult<BytesMut> {
+        packet.validate()?;
+        let mut payload = std::mem::take(&mut self.payload);
+        payload.clear();
+        packet.write(&mut payload);
+        let frame = self.encode_frame(P::PACKET_ID, P::IS_COMPRESSED, &payload);
+        self.payload = payload;
+        frame
+    }
+
+    /// Encode a frame from an ID and an uncompressed payload.
+    pub fn encode_frame(
+        &mut self,
+        packet_id: u32,
+        is_compressed: bool,
+        payload: &[u8],
+    ) -> CodecResult<BytesMut> {
+        let payload = if is_compressed && !payload.is_empty() {
+            self.compress(payload)?
+        } else {
+            payload
+        };
+
+        let mut frame = BytesMut::with_capacity(FRAME_HEADER_SIZE + payload.len());
+        frame.put_u32_le(payload.len() as u32);
+        frame.put_u32_le(packet_id);
+        frame.put_slice(payload);
+        Ok(frame)
+    }
+}
+
+/// Reusable zstd decompression context.
+pub struct Decompressor {
+    ctx: zstd_safe::DCtx<'static>,
+}
+
+impl Decompressor {
+    pub fn new() -> CodecResult<Self> {
+        let ctx = zstd_safe::DCtx::try_create()
+            .ok_or_else(|| CodecError::Other("Failed to create a zstd context".to_string()))?;
+        Ok(Self { ctx })
+    }
+
+    /// Decompress with the dictionary the peer compressed with.
+    pub fn with_dictionary(dictionary: &[u8]) -> CodecResult<Self> {
+        let mut decompressor = Self::new()?;
+        decompressor
+            .ctx
+            .load_dictionary(dictionary)
+            .map_err(zstd_error)?;
+        Ok(decompressor)
+    }
+
+    /// Decompress `data`, failing with `FrameTooLarge` as soon as the output
+    /// grows past `max_size` bytes.
+    pub fn decompress(&mut self, data: &[u8], max_size: usize) -> CodecResult<Vec<u8>> {
+        // Reject early on the size the frame header announces, without one
+        // start from a single stream buffer and grow up to the cap
+        let capacity = match zstd_safe::get_frame_content_size(data) {
+            Ok(Some(size)) if size > max_size as u64 => {
+                return Err(CodecError::FrameTooLarge {
+                    length: size as usize,
+                    max: max_size,
+                });
+            }
+            Ok(Some(size)) => size as usize,
+            _ => zstd_safe::DCtx::out_size(),
+        };
+        self.ctx
+            .reset(zstd_safe::ResetDirective::SessionOnly)
+            .map_err(zstd_error)?;
+
+        // One byte past the cap is enough to tell that the output is too large
+        let limit = max_size.saturating_add(1);
+        let mut output = Vec::with_capacity(capacity.min(limit));
+        let mut input = zstd_safe::InBuffer::around(data);
+        loop {
+            if output.len() == output.capacity() {
+                if output.len() == limit {
+                    return Err(CodecError::FrameTooLarge {
+                        length: output.len(),
+                        max: max_size,
+                    });
+                }
+                let additional = output
+                    .capacity()
+                    .max(zstd_safe::DCtx::out_size())
+                    .min(limit - output.len());
+                output.reserve_exact(additional);
+            }
+            let position = output.len();
+            let mut out = zstd_safe::OutBuffer::around_pos(&mut output, position);
+            let hint = self
+                .ctx
+                .decompress_stream(&mut out, &mut input)
+                .map_err(zstd_error)?;
+            let consumed = input.pos() == data.len();
+            if hint == 0 && consumed {
+                break;
+            }
+            // Waiting for input that will never come
+            if consumed && output.len() < output.capacity() {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        }
+        if output.len() > max_size {
+            return Err(CodecError::FrameTooLarge {
+                length: output.len(),
+                max: max_size,
+            });
+        }
+        Ok(output)
+    }
+
+    /// Decode a packet payload (excluding Length and ID), decompressing it if needed.
+    pub fn decode_payload<P: Packet>(&mut self, payload: Bytes) -> CodecResult<P> {
+        self.decode_payload_with(payload, DecodeOptions::default(), &mut Vec::new())
+    }
+
+    /// `decode_payload` with explicit options; lenient mode warnings are appended to `warnings`.
+    pub fn decode_payload_with<P: Packet>(
+        &mut self,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<P> {
+        check_payload_size(P::PACKET_ID, P::IS_COMPRESSED, payload.len(), P::MAX_SIZE)?;
+        let data = if P::IS_COMPRESSED && !payload.is_empty() {
+            self.decompress(&payload, P::MAX_SIZE)
+                .map_err(|e| too_large(P::PACKET_ID, e))?
+                .into()
+        } else {
+            payload
+        };
+
+        read_packet(data, options, warnings)
+    }
+}
+
+fn zstd_error(code: zstd_safe::ErrorCode) -> CodecError {
+    io::Error::other(zstd_safe::get_error_name(code)).into()
+}
+
+thread_local! {
+    static COMPRESSOR: RefCell<Option<Compressor>> = const { RefCell::new(None) };
+    static DECOMPRESSOR: RefCell<Option<Decompressor>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with this thread's shared compressor.
+pub(crate) fn with_compressor<T>(
+    f: impl FnOnce(&mut Compressor) -> CodecResult<T>,
+) -> CodecResult<T> {
+    COMPRESSOR.with(|compressor| match &mut *compressor.borrow_mut() {
+        Some(compressor) => f(compressor),
+        compressor @ None => f(compressor.insert(Compressor::new(DEFAULT_COMPRESSION_LEVEL)?)),
+    })
+}
+
+/// Run `f` with this thread's shared decompressor.
+pub(crate) fn with_decompressor<T>(
+    f: impl FnOnce(&mut Decompressor) -> CodecResult<T>,
+) -> CodecResult<T> {
+    DECOMPRESSOR.with(|decompressor| match &mut *decompressor.borrow_mut() {
+        Some(decompressor) => f(decompressor),
+        decompressor @ None => f(decompressor.insert(Decompressor::new()?)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompresses_with_and_without_content_size() {
+        let data: Vec<u8> = (0..300_000).map(|i| (i % 7) as u8).collect();
+        let mut compressor = Compressor::new(DEFAULT_COMPRESSION_LEVEL).unwrap();
+        let with_size = compressor.compress(&data).unwrap().to_vec();
+        // The streaming encoder doesn't know the size up front
+        let without_size = zstd::stream::encode_all(&data[..], 0).unwrap();
+        assert!(matches!(
+            zstd_safe::get_frame_content_size(&without_size),
+            Ok(None)
+        ));
+
+        let mut decompressor = Decompressor::new().unwrap();
+        for frame in [&with_size, &without_size] {
+            assert_eq!(decompressor.decompress(frame, data.len()).unwrap(), data);
+            let error = decompressor.decompress(frame, data.len() - 1).unwrap_err();
+            assert!(
+                matches!(error, CodecError::FrameTooLarge { max, .. } if max == data.len() - 1),
+                "{error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        let data = vec![1u8; 1000];
+        let frame = zstd::stream::encode_all(&data[..], 0).unwrap();
+        let mut decompressor = Decompressor::new().unwrap();
+        assert!(
+            decompressor
+                .decompress(&frame[..frame.len() - 2], 1000)
+                .is_err()
+        );
+        // The context is still usable afterwards
+        assert_eq!(decompressor.decompress(&frame, 1000).unwrap(), data);
+    }
+}
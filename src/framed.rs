@@ -0,0 +1,246 @@
+//! `tokio_util` codecs for the Hytale frame format (Length + ID + Payload).
+
+use crate::codec::{
+    CodecError, DEFAULT_MAX_FRAME_LENGTH, DecodeOptions, DecodeWarning, FRAME_HEADER_SIZE, Packet,
+    encode_packet, split_frame,
+};
+use crate::server_bound::{ServerBoundPacket, deserialize_server_bound_with};
+use bytes::{BufMut, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Splits a byte stream into `(packet_id, payload)` frames.
+///
+/// The payload is returned as it is on the wire, i.e. still compressed for
+/// packets that use Zstd.
+#[derive(Debug, Clone)]
+pub struct FrameCodec {
+    max_frame_length: usize,
+}
+
+impl FrameCodec {
+    pub fn new() -> Self {
+        Self {
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+        }
+    }
+
+    /// Reject frames whose payload is longer than `max_frame_length` bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+}
+
+impl Default for FrameCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = (u32, Bytes);
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        split_frame(src, self.max_frame_length)
+    }
+}
+
+impl Encoder<(u32, Bytes)> for FrameCodec {
+    type Error = CodecError;
+
+    fn encode(
+        &mut self,
+        (packet_id, payload): (u32, Bytes),
+        dst: &mut BytesMut,
+    ) -> Result<(), Self::Error> {
+        if payload.len() > self.max_frame_length {
+            return Err(CodecError::FrameTooLarge {
+                length: payload.len(),
+                max: self.max_frame_length,
+            });
+        }
+
+        dst.reserve(FRAME_HEADER_SIZE + payload.len());
+        dst.put_u32_le(payload.len() as u32);
+        dst.put_u32_le(packet_id);
+        dst.put(payload);
+        Ok(())
+    }
+}
+
+/// Server side codec: decodes `ServerBoundPacket`s and encodes any `Packet`.
+#[derive(Debug, Clone, Default)]
+pub struct ServerBoundCodec {
+    frames: FrameCodec,
+    decode_options: DecodeOptions,
+    warnings: Vec<DecodeWarning>,
+}
+
+impl ServerBoundCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject frames whose payload is longer than `max_frame_length` bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.frames = self.frames.with_max_frame_length(max_frame_length);
+        self
+    }
+
+    /// Decode packets strictly, or leniently while recording warnings.
+    pub fn with_decode_options(mut self, options: DecodeOptions) -> Self {
+        self.decode_options = options;
+        self
+    }
+
+    /// Warnings recorded while decoding leniently since the last call.
+    pub fn take_warnings(&mut self) -> Vec<DecodeWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+impl Decoder for ServerBoundCodec {
+    type Item = ServerBoundPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.frames.decode(src)? {
+            Some((packet_id, payload)) => deserialize_server_bound_with(
+                packet_id,
+                payload,
+                self.decode_options,
+                &mut self.warnings,
+            )
+            .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<P: Packet> Encoder<&P> for ServerBoundCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, packet: &P, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let frame = encode_packet(packet)?;
+        let length = frame.len() - FRAME_HEADER_SIZE;
+        if length > self.frames.max_frame_length() {
+            return Err(CodecError::FrameTooLarge {
+                length,
+                max: self.frames.max_frame_length(),
+            });
+        }
+        dst.extend_from_slice(&frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::connection::Ping;
+    use crate::packets::interface::{ChatMessage, ChatType};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, duplex};
+
+    fn chat(message: &str) -> ChatMessage {
+        ChatMessage {
+            message: message.to_string(),
+            chat_type: ChatType::Whisper,
+            sender_name: Some("bob".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn decodes_frames_split_across_reads() {
+        // A pipe this small hands out every frame in several pieces
+        let (mut client, mut server) = duplex(7);
+        let writer = tokio::spawn(async move {
+            let mut codec = ServerBoundCodec::new();
+            let mut buf = BytesMut::new();
+            codec.encode(&Ping { time: 42 }, &mut buf).unwrap();
+            codec
+                .encode(&chat(&"hello ".repeat(100)), &mut buf)
+                .unwrap();
+            codec.encode(&Ping { time: -1 }, &mut buf).unwrap();
+            client.write_all(&buf).await.unwrap();
+        });
+
+        let mut codec = ServerBoundCodec::new();
+        let mut buf = BytesMut::new();
+        let mut packets = Vec::new();
+        while server.read_buf(&mut buf).await.unwrap() > 0 {
+            while let Some(packet) = codec.decode(&mut buf).unwrap() {
+                packets.push(packet);
+            }
+        }
+        writer.await.unwrap();
+
+        assert!(buf.is_empty());
+        assert!(matches!(
+            packets.as_slice(),
+            [
+                ServerBoundPacket::Ping(Ping { time: 42 }),
+                ServerBoundPacket::ChatMessage(_),
+                ServerBoundPacket::Ping(Ping { time: -1 }),
+            ]
+        ));
+        let ServerBoundPacket::ChatMessage(message) = &packets[1] else {
+            unreachable!()
+        };
+        assert_eq!(message.message, "hello ".repeat(100));
+        assert_eq!(message.chat_type, ChatType::Whisper);
+        assert_eq!(message.sender_name.as_deref(), Some("bob"));
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_rest_of_a_partial_frame() {
+        let (mut client, mut server) = duplex(1024);
+        let mut frame = BytesMut::new();
+        ServerBoundCodec::new()
+            .encode(&chat("partial"), &mut frame)
+            .unwrap();
+        let mut codec = ServerBoundCodec::new();
+        let mut buf = BytesMut::new();
+
+        // Part of the header, the whole header, then half the frame
+        for end in [FRAME_HEADER_SIZE - 1, FRAME_HEADER_SIZE, frame.len() / 2] {
+            client.write_all(&frame[buf.len()..end]).await.unwrap();
+            server.read_buf(&mut buf).await.unwrap();
+            assert_eq!(buf.len(), end);
+            assert!(codec.decode(&mut buf).unwrap().is_none());
+        }
+
+        client.write_all(&frame[buf.len()..]).await.unwrap();
+        server.read_buf(&mut buf).await.unwrap();
+        let packet = codec.decode(&mut buf).unwrap();
+        assert!(
+            matches!(packet, Some(ServerBoundPacket::ChatMessage(m)) if m.message == "partial")
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_frames_from_the_header() {
+        let (mut client, mut server) = duplex(64);
+        let mut frame = BytesMut::new();
+        FrameCodec::new()
+            .encode((2, Bytes::from_static(&[0; 32])), &mut frame)
+            .unwrap();
+        client.write_all(&frame[..FRAME_HEADER_SIZE]).await.unwrap();
+
+        let mut buf = BytesMut::new();
+        server.read_buf(&mut buf).await.unwrap();
+        let result = FrameCodec::new().with_max_frame_length(16).decode(&mut buf);
+        assert!(matches!(
+            result,
+            Err(CodecError::FrameTooLarge {
+                length: 32,
+                max: 16
+            })
+        ));
+    }
+}
@@ -1,19 +1,51 @@
+// Lets `#[derive(HytalePacket)]` refer to `::hytale_protocol` from inside this crate.
+extern crate self as hytale_protocol;
+
+#[cfg(feature = "auth")]
+pub mod auth;
 pub mod block;
+pub mod capture;
 pub mod chunk;
 pub mod chunk_data;
+pub mod client_bound;
 pub mod codec;
 pub mod common;
+pub mod compression;
+#[cfg(feature = "tokio")]
+pub mod framed;
 pub mod identifier;
 pub mod packet;
 pub mod packets;
+pub mod pcap;
+pub mod proxy;
 pub mod server_bound;
+pub mod session;
+pub mod state;
 pub mod types;
+pub mod version;
+
+pub use client_bound::{
+    ClientBoundPacket, deserialize_client_bound, deserialize_client_bound_in,
+    deserialize_client_bound_sized, deserialize_client_bound_with,
+};
+pub use server_bound::{
+    ServerBoundPacket, deserialize_server_bound, deserialize_server_bound_sized,
+    deserialize_server_bound_with,
+};
+
+pub use hytale_protocol_derive::HytalePacket;
 
-pub use server_bound::{ServerBoundPacket, deserialize_server_bound};
+#[doc(hidden)]
+pub mod __private {
+    pub use bytes;
+}
 
 pub use block::{BlockRegistry, BlockState, BlockStateId};
 pub use chunk::{Chunk, ChunkSection};
-pub use codec::{PacketRead, PacketWrite};
+pub use codec::{DecodeOptions, DecodeWarning, PacketRead, PacketWrite};
 pub use identifier::Identifier;
-pub use packets::play::ChunkDataPacket;
+pub use packet::{PacketDirection, PacketInfo, PacketRegistry};
+pub use session::{ClientSession, ServerEvent, ServerSession};
+pub use state::ConnectionState;
 pub use types::{BlockPos, ChunkPos, Transform, Vec3f, Vec3i};
+pub use version::{PacketLayout, ProtocolVersion, ProtocolVersions};
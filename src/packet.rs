@@ -1,19 +1,182 @@
-use crate::codec::{PacketRead, PacketWrite};
+use crate::codec::{CodecResult, encode_packet};
+use crate::packets::auth::{AuthGrant, AuthToken, ServerAuthToken};
+use crate::packets::connection::{Connect, Disconnect, Ping, Pong};
+use crate::packets::entities::{ApplyKnockback, ChangeVelocity, EntityUpdates};
+use crate::packets::interface::{BlockChange, ChatMessage, ServerInfo};
+use crate::packets::inventory::{DropItemStack, MoveItemStack, SetActiveSlot};
+use crate::packets::player::{ClientMovement, ClientReady, ClientTeleport, JoinWorld};
+use crate::packets::setup::{
+    AssetFinalize, AssetInitialize, AssetPart, PlayerOptions, RequestAssets,
+    RequestCommonAssetsRebuild, SetClientId, SetEntitySeed, SetTimeDilation, SetUpdateRate,
+    UpdateFeatures, ViewRadius, WorldLoadFinished, WorldLoadProgress, WorldSettings,
+};
+use crate::packets::world::{PlaySoundEvent2D, PlaySoundEvent3D, ServerSetBlock, SetChunk};
+use crate::packets::worldmap::{UpdateWorldMap, UpdateWorldMapSettings};
 use bytes::BytesMut;
 
-pub type PacketId = u16;
+pub use crate::codec::Packet;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub type PacketId = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PacketDirection {
     Serverbound,
     Clientbound,
+    /// Sent by both sides with the same ID and layout (e.g. `Disconnect`).
+    Bidirectional,
+}
+
+impl PacketDirection {
+    /// Whether a packet going in `self` direction can also travel in `other` direction.
+    pub const fn overlaps(self, other: PacketDirection) -> bool {
+        matches!(
+            (self, other),
+            (PacketDirection::Bidirectional, _)
+                | (_, PacketDirection::Bidirectional)
+                | (PacketDirection::Serverbound, PacketDirection::Serverbound)
+                | (PacketDirection::Clientbound, PacketDirection::Clientbound)
+        )
+    }
+}
+
+/// Static description of a packet known to the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketInfo {
+    pub direction: PacketDirection,
+    pub id: PacketId,
+    pub name: &'static str,
+    /// Whether the payload is Zstd compressed on the wire.
+    pub compressed: bool,
+    /// Maximum size of the (decompressed) payload in bytes.
+    pub max_size: usize,
+}
+
+impl PacketInfo {
+    /// Build the registry entry of a packet from its `Packet` implementation.
+    pub const fn of<P: Packet>(name: &'static str) -> Self {
+        Self {
+            direction: P::DIRECTION,
+            id: P::PACKET_ID,
+            name,
+            compressed: P::IS_COMPRESSED,
+            max_size: P::MAX_SIZE,
+        }
+    }
+}
+
+macro_rules! packet_info {
+    ($packet:ident) => {
+        $crate::packet::PacketInfo::of::<$packet>(stringify!($packet))
+    };
 }
+pub(crate) use packet_info;
 
-pub trait Packet: PacketRead + PacketWrite + Send + Sync + 'static {
-    const ID: PacketId;
-    const DIRECTION: PacketDirection;
+/// Every packet implemented by this crate, keyed by (direction, id).
+pub const PACKETS: &[PacketInfo] = &[
+    // Both directions
+    packet_info!(Disconnect),
+    packet_info!(ChatMessage),
+    // Server bound
+    packet_info!(Connect),
+    packet_info!(Ping),
+    packet_info!(AuthToken),
+    packet_info!(RequestAssets),
+    packet_info!(ClientReady),
+    packet_info!(ClientMovement),
+    packet_info!(ClientTeleport),
+    packet_info!(PlayerOptions),
+    packet_info!(RequestCommonAssetsRebuild),
+    packet_info!(BlockChange),
+    packet_info!(SetActiveSlot),
+    packet_info!(DropItemStack),
+    packet_info!(MoveItemStack),
+    // Client bound
+    packet_info!(Pong),
+    packet_info!(AuthGrant),
+    packet_info!(ServerAuthToken),
+    packet_info!(WorldSettings),
+    packet_info!(WorldLoadProgress),
+    packet_info!(WorldLoadFinished),
+    packet_info!(AssetInitialize),
+    packet_info!(AssetPart),
+    packet_info!(AssetFinalize),
+    packet_info!(SetUpdateRate),
+    packet_info!(SetTimeDilation),
+    packet_info!(UpdateFeatures),
+    packet_info!(ViewRadius),
+    packet_info!(ApplyKnockback),
+    packet_info!(ChangeVelocity),
+    packet_info!(SetClientId),
+    packet_info!(JoinWorld),
+    packet_info!(SetChunk),
+    packet_info!(ServerSetBlock),
+    packet_info!(PlaySoundEvent2D),
+    packet_info!(PlaySoundEvent3D),
+    packet_info!(SetEntitySeed),
+    packet_info!(EntityUpdates),
+    packet_info!(ServerInfo),
+    packet_info!(UpdateWorldMapSettings),
+    packet_info!(UpdateWorldMap),
+];
+
+/// IDs that two packets travelling in the same direction both claim in the
+/// protocol as extracted from the server. `ConnectionState` tells them apart by
+/// phase; stateless decoding rejects them with `CodecError::AmbiguousPacketId`.
+pub const SHARED_IDS: &[(PacketDirection, PacketId)] = &[
+    // WorldSettings (compressed, ends the auth phase) and WorldLoadProgress (setup)
+    (PacketDirection::Clientbound, 21),
+];
+
+/// Returns true if no two packets travelling in the same direction share an ID.
+pub(crate) const fn ids_are_unique(packets: &[PacketInfo]) -> bool {
+    ids_are_unique_except(packets, &[])
 }
 
+/// `ids_are_unique`, tolerating the (direction, id) pairs listed in `shared`.
+pub(crate) const fn ids_are_unique_except(
+    packets: &[PacketInfo],
+    shared: &[(PacketDirection, PacketId)],
+) -> bool {
+    let mut i = 0;
+    while i < packets.len() {
+        let mut j = i + 1;
+        while j < packets.len() {
+            if packets[i].direction.overlaps(packets[j].direction)
+                && packets[i].id == packets[j].id
+                && !is_shared(shared, packets[i].direction, packets[i].id)
+            {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn is_shared(
+    shared: &[(PacketDirection, PacketId)],
+    direction: PacketDirection,
+    id: PacketId,
+) -> bool {
+    let mut i = 0;
+    while i < shared.len() {
+        if shared[i].0 as u8 == direction as u8 && shared[i].1 == id {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+// Fail the build if two packets in the same direction claim the same ID,
+// unless the collision is a known one listed in `SHARED_IDS`.
+const _: () = assert!(
+    ids_are_unique_except(PACKETS, SHARED_IDS),
+    "two packets in the same direction share a packet id"
+);
+
 pub struct PacketRegistry {}
 
 impl PacketRegistry {
@@ -21,13 +184,24 @@ impl PacketRegistry {
         Self {}
     }
 
-    pub fn encode<P: Packet>(&self, packet: &P) -> BytesMut {
-        let mut buf = BytesMut::new();
-        // Write packet ID as VarInt
-        crate::codec::write_varint(&mut buf, P::ID as i32);
-        // Write packet data
-        packet.write(&mut buf);
-        buf
+    /// Look up a packet by the direction it travels in and its ID.
+    ///
+    /// For the IDs in `SHARED_IDS` this is the first packet registered with
+    /// it, use `ConnectionState::get` to resolve them by phase.
+    pub fn get(&self, direction: PacketDirection, id: PacketId) -> Option<&'static PacketInfo> {
+        PACKETS
+            .iter()
+            .find(|info| info.direction.overlaps(direction) && info.id == id)
+    }
+
+    /// Iterate over every registered packet.
+    pub fn iter(&self) -> impl Iterator<Item = &'static PacketInfo> {
+        PACKETS.iter()
+    }
+
+    /// Encode a packet into a complete Hytale frame (Length + ID + Payload).
+    pub fn encode<P: Packet>(&self, packet: &P) -> CodecResult<BytesMut> {
+        encode_packet(packet)
     }
 }
 
@@ -1,6 +1,9 @@
-use crate::codec::{write_string, write_varint};
+use crate::HytalePacket;
+use crate::codec::{PacketWrite, write_string, write_varint};
 use bytes::{BufMut, BytesMut};
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockType {
     pub item: Option<String>,
     pub name: String,
@@ -12,123 +15,38 @@ pub struct BlockType {
     pub cube_textures: Option<Vec<BlockTextures>>,
 }
 
+/// Texture of each face of a cube block. Faces without a texture are null.
+#[derive(Debug, Clone, PartialEq, HytalePacket)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockTextures {
-    pub top: String,
-    pub bottom: String,
-    pub front: String,
-    pub back: String,
-    pub left: String,
-    pub right: String,
+    #[hytale(nullable, offset, var_string)]
+    pub top: Option<String>,
+    #[hytale(nullable, offset, var_string)]
+    pub bottom: Option<String>,
+    #[hytale(nullable, offset, var_string)]
+    pub front: Option<String>,
+    #[hytale(nullable, offset, var_string)]
+    pub back: Option<String>,
+    #[hytale(nullable, offset, var_string)]
+    pub left: Option<String>,
+    #[hytale(nullable, offset, var_string)]
+    pub right: Option<String>,
     pub weight: f32,
 }
 
 impl BlockTextures {
-    pub fn write(&self, buf: &mut BytesMut) {
-        use crate::codec::write_string;
-        use bytes::BufMut;
-
-        // BlockTextures Layout:
-        // 0: nullBits (1 byte)
-        // 1: weight (4 bytes)
-        // 5: offsets (6 * 4 = 24 bytes) (variable strings)
-        // 29: variable data start
-
-        let start_pos = buf.len();
-
-        buf.put_u8(0);
-
-        buf.put_f32_le(self.weight);
-
-        let offsets_start = buf.len(); // should be start_pos + 5
-        buf.resize(offsets_start + 24, 0xFF);
-
-        let var_start = buf.len(); // should be start_pos + 29
-
-        let mut null_bits = 0u8;
-
-        // ID 0: Top
-        // ID 1: Bottom
-        // ID 2: Front
-        // ID 3: Back
-        // ID 4: Left
-        // ID 5: Right
-
-        // Field IDs correspond to: 1<<0, 1<<1, 1<<2...
-
-        // Top (ID 0)
-        if !self.top.is_empty() {
-            null_bits |= 1;
-            let current_pos = buf.len();
-            let rel_off = (current_pos - var_start) as i32;
-            let off_idx = offsets_start + (0 * 4);
-            let mut t = BytesMut::with_capacity(4);
-            t.put_i32_le(rel_off);
-            buf[off_idx..off_idx + 4].copy_from_slice(&t);
-            write_string(buf, &self.top);
-        }
-
-        // Bottom (ID 1)
-        if !self.bottom.is_empty() {
-            null_bits |= 2;
-            let current_pos = buf.len();
-            let rel_off = (current_pos - var_start) as i32;
-            let off_idx = offsets_start + (1 * 4);
-            let mut t = BytesMut::with_capacity(4);
-            t.put_i32_le(rel_off);
-            buf[off_idx..off_idx + 4].copy_from_slice(&t);
-            write_string(buf, &self.bottom);
-        }
-
-        // Front (ID 2)
-        if !self.front.is_empty() {
-            null_bits |= 4;
-            let current_pos = buf.len();
-            let rel_off = (current_pos - var_start) as i32;
-            let off_idx = offsets_start + (2 * 4);
-            let mut t = BytesMut::with_capacity(4);
-            t.put_i32_le(rel_off);
-            buf[off_idx..off_idx + 4].copy_from_slice(&t);
-            write_string(buf, &self.front);
-        }
-
-        // Back (ID 3)
-        if !self.back.is_empty() {
-            null_bits |= 8;
-            let current_pos = buf.len();
-            let rel_off = (current_pos - var_start) as i32;
-            let off_idx = offsets_start + (3 * 4);
-            let mut t = BytesMut::with_capacity(4);
-            t.put_i32_le(rel_off);
-            buf[off_idx..off_idx + 4].copy_from_slice(&t);
-            write_string(buf, &self.back);
-        }
-
-        // Left (ID 4)
-        if !self.left.is_empty() {
-            null_bits |= 16;
-            let current_pos = buf.len();
-            let rel_off = (current_pos - var_start) as i32;
-            let off_idx = offsets_start + (4 * 4);
-            let mut t = BytesMut::with_capacity(4);
-            t.put_i32_le(rel_off);
-            buf[off_idx..off_idx + 4].copy_from_slice(&t);
-            write_string(buf, &self.left);
-        }
-
-        // Right (ID 5)
-        if !self.right.is_empty() {
-            null_bits |= 32;
-            let current_pos = buf.len();
-            let rel_off = (current_pos - var_start) as i32;
-            let off_idx = offsets_start + (5 * 4);
-            let mut t = BytesMut::with_capacity(4);
-            t.put_i32_le(rel_off);
-            buf[off_idx..off_idx + 4].copy_from_slice(&t);
-            write_string(buf, &self.right);
+    /// The same texture on every face.
+    pub fn uniform(texture: &str) -> Self {
+        let texture = Some(texture.to_string());
+        Self {
+            top: texture.clone(),
+            bottom: texture.clone(),
+            front: texture.clone(),
+            back: texture.clone(),
+            left: texture.clone(),
+            right: texture,
+            weight: 1.0,
         }
-
-        // Write NullBits
-        buf[start_pos] = null_bits;
     }
 }
 
@@ -159,15 +77,7 @@ impl BlockType {
             draw_type: 2, // Cube (was 1/GizmoCube)
             material: 1,  // Solid
             opacity: 0,   // Solid
-            cube_textures: Some(vec![BlockTextures {
-                top: "BlockTextures/Unknown.png".to_string(),
-                bottom: "BlockTextures/Unknown.png".to_string(),
-                front: "BlockTextures/Unknown.png".to_string(),
-                back: "BlockTextures/Unknown.png".to_string(),
-                left: "BlockTextures/Unknown.png".to_string(),
-                right: "BlockTextures/Unknown.png".to_string(),
-                weight: 1.0,
-            }]),
+            cube_textures: Some(vec![BlockTextures::uniform("BlockTextures/Unknown.png")]),
         }
     }
 
@@ -180,13 +90,8 @@ impl BlockType {
             material: 1,  // Solid
             opacity: 0,   // Solid
             cube_textures: Some(vec![BlockTextures {
-                top: "BlockTextures/Calcite_Top.png".to_string(),
-                bottom: "BlockTextures/Calcite.png".to_string(),
-                front: "BlockTextures/Calcite.png".to_string(),
-                back: "BlockTextures/Calcite.png".to_string(),
-                left: "BlockTextures/Calcite.png".to_string(),
-                right: "BlockTextures/Calcite.png".to_string(),
-                weight: 1.0,
+                top: Some("BlockTextures/Calcite_Top.png".to_string()),
+                ..BlockTextures::uniform("BlockTextures/Calcite.png")
             }]),
         }
     }
@@ -199,15 +104,7 @@ impl BlockType {
             draw_type: 2, // Cube
             material: 1,  // Solid
             opacity: 0,   // Solid
-            cube_textures: Some(vec![BlockTextures {
-                top: tex.to_string(),
-                bottom: tex.to_string(),
-                front: tex.to_string(),
-                back: tex.to_string(),
-                left: tex.to_string(),
-                right: tex.to_string(),
-                weight: 1.0,
-            }]),
+            cube_textures: Some(vec![BlockTextures::uniform(tex)]),
         }
     }
 
@@ -215,7 +112,6 @@ impl BlockType {
         let start_pos = buf.len();
 
         // NullBits (4 bytes) - Placeholder, we fill at end
-        let null_bits_idx = start_pos;
         buf.put_u32_le(0);
 
         // 2. Fixed Block (bytes 4..163) (Length 159)
@@ -329,7 +225,7 @@ impl BlockType {
             let current_pos = buf.len();
             let relative_offset = (current_pos - var_data_start_idx) as i32;
             // Write offset at index 0 (0 * 4 = 0 bytes offset from start of table)
-            let off_pos = offsets_start_idx + 0;
+            let off_pos = offsets_start_idx;
             let mut temp = BytesMut::with_capacity(4);
             temp.put_i32_le(relative_offset);
             let range = off_pos..off_pos + 4;
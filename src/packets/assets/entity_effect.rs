@@ -1,6 +1,8 @@
 use crate::codec::write_string;
 use bytes::{BufMut, BytesMut};
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EntityEffect {
     pub id: Option<String>,
     pub name: Option<String>,
@@ -105,7 +107,7 @@ impl EntityEffect {
             null_bits |= 1;
             let current_pos = buf.len();
             let rel_off = (current_pos - var_start_idx) as i32;
-            let off_idx = offsets_start_idx + 0;
+            let off_idx = offsets_start_idx;
             let mut t = BytesMut::with_capacity(4);
             t.put_i32_le(rel_off);
             buf[off_idx..off_idx + 4].copy_from_slice(&t);
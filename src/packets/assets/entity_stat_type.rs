@@ -1,6 +1,8 @@
 use crate::codec::write_string;
 use bytes::{BufMut, BytesMut};
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EntityStatType {
     pub id: Option<String>,
     pub value: f32,
@@ -58,7 +60,6 @@ impl EntityStatType {
         // We need space for 1 + 13 (Fixed) + 12 (Offsets) = 26 bytes total header.
         // Wait, buf.put_u8(0) wrote 1 byte.
         // We need 25 more bytes.
-        let fixed_start = start_pos; // including nullbits
 
         // Value (4 bytes)
         buf.put_f32_le(self.value);
@@ -82,7 +83,7 @@ impl EntityStatType {
             null_bits |= 1;
             let current_pos = buf.len();
             let rel_off = (current_pos - var_start_idx) as i32;
-            let off_idx = offsets_start_idx + 0;
+            let off_idx = offsets_start_idx;
             let mut t = BytesMut::with_capacity(4);
             t.put_i32_le(rel_off);
             buf[off_idx..off_idx + 4].copy_from_slice(&t);
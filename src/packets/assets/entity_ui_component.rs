@@ -1,5 +1,7 @@
 use bytes::{BufMut, BytesMut};
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EntityUIComponent {
     pub type_: u8, // Enum: EntityStat(0), CombatText(1)
 
@@ -114,7 +116,7 @@ impl EntityUIComponent {
         }
 
         // Variable Data
-        if let Some(ref events) = self.combat_text_animation_events {
+        if self.combat_text_animation_events.is_some() {
             // Write array length + data
             // We assume 'events' is raw serialized bytes including VarInt length?
             // Or we write length here?
@@ -1,6 +1,8 @@
 use crate::codec::write_string;
 use bytes::{BufMut, BytesMut};
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     pub id: Option<String>,
     pub block_id: i32,
@@ -5,3 +5,43 @@ pub mod server_auth_token;
 pub use auth_grant::*;
 pub use auth_token::*;
 pub use server_auth_token::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::round_trip;
+    use bytes::Bytes;
+
+    #[test]
+    fn auth_grant() {
+        let grant = round_trip(&AuthGrant {
+            authorization_grant: Some("grant".to_string()),
+            server_identity_token: Some("identity".to_string()),
+        });
+        assert_eq!(grant.authorization_grant.as_deref(), Some("grant"));
+        assert_eq!(grant.server_identity_token.as_deref(), Some("identity"));
+    }
+
+    #[test]
+    fn auth_token() {
+        let token = round_trip(&AuthToken {
+            access_token: Some("access".to_string()),
+            server_authorization_grant: None,
+        });
+        assert_eq!(token.access_token.as_deref(), Some("access"));
+        assert_eq!(token.server_authorization_grant, None);
+    }
+
+    #[test]
+    fn server_auth_token() {
+        let token = round_trip(&ServerAuthToken {
+            server_access_token: None,
+            password_challenge: Some(Bytes::from_static(&[0xDE, 0xAD, 0xBE, 0xEF])),
+        });
+        assert_eq!(token.server_access_token, None);
+        assert_eq!(
+            token.password_challenge.as_deref(),
+            Some(&[0xDE, 0xAD, 0xBE, 0xEF][..])
+        );
+    }
+}
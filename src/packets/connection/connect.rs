@@ -1,45 +1,95 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_string};
-use bytes::{BufMut, BytesMut};
+use crate::HytalePacket;
+use crate::codec::{
+    CodecError, CodecResult, MAX_VAR_STRING_SIZE, Packet, PacketBuffer, PacketRead, PacketWrite,
+};
+use crate::packet::PacketDirection;
+use bytes::{BufMut, Bytes, BytesMut};
 use uuid::Uuid;
 
+/// Length of the protocol hash, sent as a zero padded ASCII string.
+pub const PROTOCOL_HASH_LENGTH: usize = 64;
+
+/// Largest `Connect.referral_data` the server accepts.
+pub const MAX_REFERRAL_DATA_LENGTH: usize = 4096;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ClientType {
     Game = 0,
     Editor = 1,
 }
 
-#[derive(Debug, Clone)]
+impl PacketRead for ClientType {
+    fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
+        match buf.read_u8()? {
+            0 => Ok(ClientType::Game),
+            1 => Ok(ClientType::Editor),
+            other => buf.unknown_variant("ClientType", other as i32, ClientType::Game),
+        }
+    }
+}
+
+impl PacketWrite for ClientType {
+    fn write(&self, buf: &mut BytesMut) {
+        buf.put_u8(*self as u8);
+    }
+}
+
+/// Address of the server that referred the client here.
+#[derive(Debug, Clone, PartialEq, Eq, HytalePacket)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostAddress {
+    pub port: u16,
+    #[hytale(var_string)]
+    pub host: String,
+}
+
+#[derive(Debug, Clone, PartialEq, HytalePacket)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Connect {
+    #[hytale(fixed_size = PROTOCOL_HASH_LENGTH)]
     pub protocol_hash: String,
+    pub client_type: ClientType,
+    #[hytale(nullable, offset, var_string)]
+    pub language: Option<String>,
+    #[hytale(nullable, offset, var_string)]
+    pub identity_token: Option<String>,
     pub uuid: Uuid,
+    #[hytale(offset, var_string)]
     pub username: String,
-    pub client_type: ClientType,
-}
-
-impl Packet for Connect {
-    const PACKET_ID: u32 = 0;
+    #[hytale(nullable, offset)]
+    pub referral_data: Option<Bytes>,
+    #[hytale(nullable, offset)]
+    pub referral_source: Option<HostAddress>,
 }
 
-impl PacketRead for Connect {
-    fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
-        // Simplified read for now to not break hytale-net
-        Ok(Self {
-            protocol_hash: String::new(),
-            uuid: Uuid::nil(),
-            username: String::new(),
-            client_type: ClientType::Game,
-        })
+impl Connect {
+    /// Check the protocol hash sent by the client against the one we speak.
+    pub fn check_protocol_hash(&self, expected: &str) -> CodecResult<()> {
+        if self.protocol_hash != expected {
+            return Err(CodecError::ProtocolHashMismatch {
+                expected: expected.to_string(),
+                actual: self.protocol_hash.clone(),
+            });
+        }
+        Ok(())
     }
 }
 
-impl PacketWrite for Connect {
-    fn write(&self, buf: &mut BytesMut) {
-        write_string(buf, &self.protocol_hash);
-        // Write UUID as big-endian (MostSig, LeastSig)
-        buf.put_u64((self.uuid.as_u128() >> 64) as u64);
-        buf.put_u64(self.uuid.as_u128() as u64);
-        write_string(buf, &self.username);
-        buf.put_u8(self.client_type as u8);
-    }
+impl Packet for Connect {
+    const PACKET_ID: u32 = 0;
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    // nullBits, protocol hash, client type, uuid and five offsets, then
+    // language, identity token, username, referral data and source
+    const MAX_SIZE: usize = 1
+        + PROTOCOL_HASH_LENGTH
+        + 1
+        + 16
+        + 5 * 4
+        + 3 * MAX_VAR_STRING_SIZE
+        + 5
+        + MAX_REFERRAL_DATA_LENGTH
+        + 2
+        + MAX_VAR_STRING_SIZE;
 }
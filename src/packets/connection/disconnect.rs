@@ -1,7 +1,11 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::codec::{
+    CodecResult, MAX_VAR_STRING_SIZE, Packet, PacketBuffer, PacketRead, PacketWrite, write_string,
+};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum DisconnectType {
     Leave = 0,
@@ -9,28 +13,46 @@ pub enum DisconnectType {
     ServerShutdown = 2,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Disconnect {
+    pub reason: Option<String>,
     pub disconnect_type: DisconnectType,
 }
 
 impl Packet for Disconnect {
     const PACKET_ID: u32 = 1;
+    const DIRECTION: PacketDirection = PacketDirection::Bidirectional;
+    const MAX_SIZE: usize = 2 + MAX_VAR_STRING_SIZE;
 }
 
 impl PacketRead for Disconnect {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
-        let disconnect_type = match buf.read_u8()? {
-            1 => DisconnectType::Kick,
-            2 => DisconnectType::ServerShutdown,
-            _ => DisconnectType::Leave,
+        let null_bits = buf.read_u8()?;
+        let disconnect_type = buf.field("disconnect_type", |buf| match buf.read_u8()? {
+            0 => Ok(DisconnectType::Leave),
+            1 => Ok(DisconnectType::Kick),
+            2 => Ok(DisconnectType::ServerShutdown),
+            other => buf.unknown_variant("DisconnectType", other as i32, DisconnectType::Leave),
+        })?;
+        let reason = if (null_bits & 1) != 0 {
+            Some(buf.field("reason", |buf| buf.read_var_string())?)
+        } else {
+            None
         };
-        Ok(Self { disconnect_type })
+        Ok(Self {
+            reason,
+            disconnect_type,
+        })
     }
 }
 
 impl PacketWrite for Disconnect {
     fn write(&self, buf: &mut BytesMut) {
+        buf.put_u8(self.reason.is_some() as u8);
         buf.put_u8(self.disconnect_type as u8);
+        if let Some(reason) = &self.reason {
+            write_string(buf, reason);
+        }
     }
 }
@@ -7,3 +7,146 @@ pub use connect::*;
 pub use disconnect::*;
 pub use ping::*;
 pub use pong::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::round_trip;
+    use bytes::Bytes;
+    use uuid::Uuid;
+
+    #[test]
+    fn connect() {
+        let connect = round_trip(&Connect {
+            protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Editor,
+            language: Some("en-US".to_string()),
+            identity_token: None,
+            uuid: Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef),
+            username: "Player".to_string(),
+            referral_data: Some(Bytes::from_static(&[1, 2, 3])),
+            referral_source: Some(HostAddress {
+                port: 5520,
+                host: "play.example.com".to_string(),
+            }),
+        });
+        assert_eq!(connect.protocol_hash, "a".repeat(PROTOCOL_HASH_LENGTH));
+        assert_eq!(connect.client_type, ClientType::Editor);
+        assert_eq!(connect.language.as_deref(), Some("en-US"));
+        assert_eq!(connect.identity_token, None);
+        assert_eq!(connect.username, "Player");
+        assert_eq!(connect.referral_data.as_deref(), Some(&[1, 2, 3][..]));
+        assert_eq!(connect.referral_source.unwrap().port, 5520);
+    }
+
+    #[test]
+    fn connect_with_every_field_at_its_maximum() {
+        use crate::codec::MAX_STRING_LENGTH;
+
+        let string = || "x".repeat(MAX_STRING_LENGTH);
+        let connect = round_trip(&Connect {
+            protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Game,
+            language: Some(string()),
+            identity_token: Some(string()),
+            uuid: Uuid::nil(),
+            username: string(),
+            referral_data: Some(vec![7; MAX_REFERRAL_DATA_LENGTH].into()),
+            referral_source: Some(HostAddress {
+                port: 5520,
+                host: string(),
+            }),
+        });
+        assert_eq!(connect.identity_token.unwrap().len(), MAX_STRING_LENGTH);
+    }
+
+    #[test]
+    fn unknown_client_type() {
+        use crate::codec::{
+            CodecError, DecodeOptions, DecodeWarningKind, decode_payload_with, encode_packet,
+        };
+
+        let mut frame = encode_packet(&Connect {
+            protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Game,
+            language: None,
+            identity_token: None,
+            uuid: Uuid::nil(),
+            username: "Player".to_string(),
+            referral_data: None,
+            referral_source: None,
+        })
+        .unwrap();
+        // Frame header, nullBits, protocol hash
+        frame[8 + 1 + PROTOCOL_HASH_LENGTH] = 7;
+        let payload = frame.freeze().slice(8..);
+
+        let mut warnings = Vec::new();
+        let connect: Connect =
+            decode_payload_with(payload.clone(), DecodeOptions::LENIENT, &mut warnings).unwrap();
+        assert_eq!(connect.client_type, ClientType::Game);
+        assert_eq!(
+            warnings[0].kind,
+            DecodeWarningKind::UnknownEnumVariant {
+                name: "ClientType",
+                value: 7
+            }
+        );
+
+        let error = decode_payload_with::<Connect>(payload, DecodeOptions::STRICT, &mut warnings)
+            .unwrap_err();
+        let CodecError::Decode(error) = error else {
+            panic!("{error:?}")
+        };
+        assert_eq!(error.path, ["client_type"]);
+        assert!(matches!(error.source, CodecError::InvalidEnumVariant(7)));
+    }
+
+    #[test]
+    fn disconnect() {
+        let disconnect = round_trip(&Disconnect {
+            reason: Some("Server closed".to_string()),
+            disconnect_type: DisconnectType::ServerShutdown,
+        });
+        assert_eq!(disconnect.reason.as_deref(), Some("Server closed"));
+        assert_eq!(disconnect.disconnect_type, DisconnectType::ServerShutdown);
+
+        let disconnect = round_trip(&Disconnect {
+            reason: None,
+            disconnect_type: DisconnectType::Leave,
+        });
+        assert_eq!(disconnect.reason, None);
+    }
+
+    #[test]
+    fn ping_pong() {
+        assert_eq!(round_trip(&Ping { time: i64::MIN }).time, i64::MIN);
+        let pong = round_trip(&Pong {
+            pong_type: PongType::Pong,
+            time: 1_234_567,
+        });
+        assert_eq!(pong.time, 1_234_567);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn connect_json() {
+        let json = crate::packets::json_round_trip(&Connect {
+            protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Editor,
+            language: Some("en-US".to_string()),
+            identity_token: None,
+            uuid: Uuid::from_u128(1),
+            username: "Player".to_string(),
+            referral_data: Some(Bytes::from_static(&[1, 2, 3])),
+            referral_source: Some(HostAddress {
+                port: 5520,
+                host: "play.example.com".to_string(),
+            }),
+        });
+        assert_eq!(json["username"], "Player");
+        assert_eq!(json["uuid"], "00000000-0000-0000-0000-000000000001");
+        assert_eq!(json["referral_data"], serde_json::json!([1, 2, 3]));
+        assert_eq!(json["referral_source"]["port"], 5520);
+    }
+}
@@ -1,19 +1,23 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ping {
     pub time: i64,
 }
 
 impl Packet for Ping {
     const PACKET_ID: u32 = 2;
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const MAX_SIZE: usize = 8;
 }
 
 impl PacketRead for Ping {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            time: buf.read_i64()?,
+            time: buf.field("time", |buf| buf.read_i64_le())?,
         })
     }
 }
@@ -1,13 +1,16 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PongType {
     Pong = 0,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pong {
     pub pong_type: PongType,
     pub time: i64,
@@ -15,16 +18,19 @@ pub struct Pong {
 
 impl Packet for Pong {
     const PACKET_ID: u32 = 3;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 9;
 }
 
 impl PacketRead for Pong {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
-        let pong_type = match buf.read_u8()? {
-            _ => PongType::Pong,
-        };
+        let pong_type = buf.field("pong_type", |buf| match buf.read_u8()? {
+            0 => Ok(PongType::Pong),
+            other => buf.unknown_variant("PongType", other as i32, PongType::Pong),
+        })?;
         Ok(Self {
             pong_type,
-            time: buf.read_i64()?,
+            time: buf.field("time", |buf| buf.read_i64_le())?,
         })
     }
 }
@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ApplyKnockback {
     pub entity_id: i32,
     pub velocity_x: f32,
@@ -11,15 +13,17 @@ pub struct ApplyKnockback {
 
 impl Packet for ApplyKnockback {
     const PACKET_ID: u32 = 88;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 16;
 }
 
 impl PacketRead for ApplyKnockback {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            entity_id: buf.read_int_le()?,
-            velocity_x: buf.read_f32()?,
-            velocity_y: buf.read_f32()?,
-            velocity_z: buf.read_f32()?,
+            entity_id: buf.field("entity_id", |buf| buf.read_int_le())?,
+            velocity_x: buf.field("velocity_x", |buf| buf.read_f32_le())?,
+            velocity_y: buf.field("velocity_y", |buf| buf.read_f32_le())?,
+            velocity_z: buf.field("velocity_z", |buf| buf.read_f32_le())?,
         })
     }
 }
@@ -1,74 +1,119 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_varint};
-use bytes::{BufMut, BytesMut};
-
-#[derive(Debug, Clone)]
+use crate::codec::{
+    CodecError, CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, VarBlockReader,
+    write_varint,
+};
+use crate::packet::PacketDirection;
+use bytes::{BufMut, Bytes, BytesMut};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EntityUpdates {
     pub removed_entities: Vec<i32>,
+    /// The entity updates, **cut short after the first one with component
+    /// updates**.
+    ///
+    /// Component updates are not decoded, so the updates of the entities that
+    /// follow them stay inside that entity's `ComponentUpdates::data`, and
+    /// `ComponentUpdates::following` says how many there are. Only when no
+    /// entity has component updates does this list hold every update.
     pub updates: Vec<EntityUpdate>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EntityUpdate {
     pub network_id: i32,
     pub removed_components: Vec<u8>,
-    pub updated_components: Vec<u8>, // Raw component data for now
+    pub updated_components: Option<ComponentUpdates>,
+}
+
+/// Component updates of an entity, kept as they are on the wire.
+///
+/// Their layout depends on the component type and they are not decoded, so
+/// where they end can't be told either: `data` runs to the end of the update
+/// list and carries the updates of the entities that follow with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentUpdates {
+    /// Number of component updates.
+    pub count: i32,
+    /// The component updates, followed by the `following` entity updates.
+    pub data: Bytes,
+    /// Number of entity updates after this one that are part of `data`.
+    ///
+    /// Counted in the update list length when writing, so it must match
+    /// `data`; only the last of `EntityUpdates::updates` may have any.
+    pub following: usize,
 }
 
+/// Smallest entity update on the wire: nullBits, network id and two offsets.
+const ENTITY_UPDATE_MIN_SIZE: usize = 13;
+
 impl Packet for EntityUpdates {
     const PACKET_ID: u32 = 161;
-    fn is_compressed() -> bool {
-        true
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const IS_COMPRESSED: bool = true;
+    const MAX_SIZE: usize = 50 * 1024 * 1024;
+
+    fn validate(&self) -> CodecResult<()> {
+        let last = self.updates.len().saturating_sub(1);
+        for (i, update) in self.updates.iter().enumerate() {
+            let Some(components) = &update.updated_components else {
+                continue;
+            };
+            if components.following > 0 && i != last {
+                return Err(CodecError::Other(format!(
+                    "Entity update {i} has {} following updates but isn't the last one",
+                    components.following
+                )));
+            }
+            // Cheap bound, the component updates themselves can't be measured
+            if components.following > components.data.len() / ENTITY_UPDATE_MIN_SIZE {
+                return Err(CodecError::Other(format!(
+                    "{} following entity updates don't fit in {} bytes of component data",
+                    components.following,
+                    components.data.len()
+                )));
+            }
+        }
+        Ok(())
     }
 }
 
 impl PacketRead for EntityUpdates {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
-        // Offsets
-        buf.read_int_le()?;
-        buf.read_int_le()?;
-
+        let mut var_block = VarBlockReader::new(buf, 2)?;
         let mut removed_entities = Vec::new();
         if (null_bits & 1) != 0 {
-            let count = buf.read_varint()? as usize;
-            for _ in 0..count {
-                removed_entities.push(buf.read_int_le()?);
-            }
+            removed_entities = var_block.read_field(0, |buf| {
+                buf.field("removed_entities", |buf| {
+                    let count = buf.read_count(4)?;
+                    (0..count).map(|_| buf.read_int_le()).collect()
+                })
+            })?;
         }
-
         let mut updates = Vec::new();
         if (null_bits & 2) != 0 {
-            let count = buf.read_varint()? as usize;
-            for _ in 0..count {
-                let u_null_bits = buf.read_u8()?;
-                let network_id = buf.read_int_le()?;
-                buf.read_int_le()?; // removed offset
-                buf.read_int_le()?; // updates offset
-
-                let mut removed_components = Vec::new();
-                if (u_null_bits & 1) != 0 {
-                    let c_count = buf.read_varint()? as usize;
-                    for _ in 0..c_count {
-                        removed_components.push(buf.read_u8()?);
+            updates = var_block.read_field(1, |buf| {
+                buf.field("updates", |buf| {
+                    let count = buf.read_count(ENTITY_UPDATE_MIN_SIZE)?;
+                    let mut updates = Vec::with_capacity(count);
+                    for i in 0..count {
+                        let mut update = EntityUpdate::read(buf)?;
+                        // The rest of the list went with the component updates
+                        if let Some(components) = &mut update.updated_components {
+                            components.following = count - i - 1;
+                            updates.push(update);
+                            break;
+                        }
+                        updates.push(update);
                     }
-                }
-
-                let mut updated_components = Vec::new();
-                if (u_null_bits & 2) != 0 {
-                    // For now, we don't fully parse components, just read remaining or next VarInt length
-                    // In real Hytale, this is very complex.
-                    let _c_count = buf.read_varint()? as usize;
-                    // Skip for now or read as raw bytes if possible
-                }
-
-                updates.push(EntityUpdate {
-                    network_id,
-                    removed_components,
-                    updated_components,
-                });
-            }
+                    Ok(updates)
+                })
+            })?;
         }
-
+        var_block.finish(buf);
         Ok(Self {
             removed_entities,
             updates,
@@ -76,6 +121,43 @@ impl PacketRead for EntityUpdates {
     }
 }
 
+impl PacketRead for EntityUpdate {
+    fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
+        let null_bits = buf.read_u8()?;
+        let network_id = buf.field("network_id", |buf| buf.read_int_le())?;
+        let mut var_block = VarBlockReader::new(buf, 2)?;
+        let mut removed_components = Vec::new();
+        if (null_bits & 1) != 0 {
+            removed_components = var_block.read_field(0, |buf| {
+                buf.field("removed_components", |buf| {
+                    let count = buf.read_count(1)?;
+                    (0..count).map(|_| buf.read_u8()).collect()
+                })
+            })?;
+        }
+        let mut updated_components = None;
+        if (null_bits & 2) != 0 {
+            updated_components = Some(var_block.read_field(1, |buf| {
+                buf.field("updated_components", |buf| {
+                    let count = buf.read_varint()?;
+                    let data = buf.read_bytes(buf.remaining())?;
+                    Ok(ComponentUpdates {
+                        count,
+                        data,
+                        following: 0,
+                    })
+                })
+            })?);
+        }
+        var_block.finish(buf);
+        Ok(Self {
+            network_id,
+            removed_components,
+            updated_components,
+        })
+    }
+}
+
 impl PacketWrite for EntityUpdates {
     fn write(&self, buf: &mut BytesMut) {
         let mut null_bits = 0u8;
@@ -86,10 +168,8 @@ impl PacketWrite for EntityUpdates {
             null_bits |= 2;
         }
         buf.put_u8(null_bits);
-
         let mut field_data = BytesMut::new();
         let mut offsets = [0i32; 2];
-
         if !self.removed_entities.is_empty() {
             offsets[0] = field_data.len() as i32;
             write_varint(&mut field_data, self.removed_entities.len() as i32);
@@ -99,48 +179,58 @@ impl PacketWrite for EntityUpdates {
         } else {
             offsets[0] = -1;
         }
-
         if !self.updates.is_empty() {
             offsets[1] = field_data.len() as i32;
-            write_varint(&mut field_data, self.updates.len() as i32);
+            let count: usize = self
+                .updates
+                .iter()
+                .map(|update| {
+                    1 + update
+                        .updated_components
+                        .as_ref()
+                        .map_or(0, |components| components.following)
+                })
+                .sum();
+            write_varint(&mut field_data, count as i32);
             for update in &self.updates {
-                let mut u_null_bits = 0u8;
-                if !update.removed_components.is_empty() {
-                    u_null_bits |= 1;
-                }
-                if !update.updated_components.is_empty() {
-                    u_null_bits |= 2;
-                }
-                field_data.put_u8(u_null_bits);
-                field_data.put_i32_le(update.network_id);
-
-                let mut u_field_data = BytesMut::new();
-                let mut u_offsets = [0i32; 2];
-
-                if !update.removed_components.is_empty() {
-                    u_offsets[0] = u_field_data.len() as i32;
-                    write_varint(&mut u_field_data, update.removed_components.len() as i32);
-                    u_field_data.put_slice(&update.removed_components);
-                } else {
-                    u_offsets[0] = -1;
-                }
-
-                if !update.updated_components.is_empty() {
-                    u_offsets[1] = u_field_data.len() as i32;
-                    write_varint(&mut u_field_data, 1); // Mock 1 component
-                    u_field_data.put_slice(&update.updated_components);
-                } else {
-                    u_offsets[1] = -1;
-                }
-
-                field_data.put_i32_le(u_offsets[0]);
-                field_data.put_i32_le(u_offsets[1]);
-                field_data.put_slice(&u_field_data);
+                update.write(&mut field_data);
             }
         } else {
             offsets[1] = -1;
         }
+        buf.put_i32_le(offsets[0]);
+        buf.put_i32_le(offsets[1]);
+        buf.put_slice(&field_data);
+    }
+}
 
+impl PacketWrite for EntityUpdate {
+    fn write(&self, buf: &mut BytesMut) {
+        let mut null_bits = 0u8;
+        if !self.removed_components.is_empty() {
+            null_bits |= 1;
+        }
+        if self.updated_components.is_some() {
+            null_bits |= 2;
+        }
+        buf.put_u8(null_bits);
+        buf.put_i32_le(self.network_id);
+        let mut field_data = BytesMut::new();
+        let mut offsets = [0i32; 2];
+        if !self.removed_components.is_empty() {
+            offsets[0] = field_data.len() as i32;
+            write_varint(&mut field_data, self.removed_components.len() as i32);
+            field_data.put_slice(&self.removed_components);
+        } else {
+            offsets[0] = -1;
+        }
+        if let Some(components) = &self.updated_components {
+            offsets[1] = field_data.len() as i32;
+            write_varint(&mut field_data, components.count);
+            field_data.put_slice(&components.data);
+        } else {
+            offsets[1] = -1;
+        }
         buf.put_i32_le(offsets[0]);
         buf.put_i32_le(offsets[1]);
         buf.put_slice(&field_data);
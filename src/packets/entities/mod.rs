@@ -5,3 +5,155 @@ pub mod entity_updates;
 pub use apply_knockback::*;
 pub use change_velocity::*;
 pub use entity_updates::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::round_trip;
+
+    #[test]
+    fn entity_updates() {
+        let updates = round_trip(&EntityUpdates {
+            removed_entities: vec![1, 2, 3],
+            updates: vec![
+                EntityUpdate {
+                    network_id: 10,
+                    removed_components: vec![4, 5],
+                    updated_components: None,
+                },
+                EntityUpdate {
+                    network_id: 11,
+                    removed_components: vec![6],
+                    updated_components: None,
+                },
+            ],
+        });
+        assert_eq!(updates.removed_entities, [1, 2, 3]);
+        assert_eq!(updates.updates.len(), 2);
+        assert_eq!(updates.updates[0].network_id, 10);
+        assert_eq!(updates.updates[0].removed_components, [4, 5]);
+        assert_eq!(updates.updates[1].network_id, 11);
+        assert_eq!(updates.updates[1].removed_components, [6]);
+
+        let updates = round_trip(&EntityUpdates {
+            removed_entities: Vec::new(),
+            updates: Vec::new(),
+        });
+        assert!(updates.removed_entities.is_empty() && updates.updates.is_empty());
+    }
+
+    #[test]
+    fn entity_updates_with_components() {
+        use crate::codec::{
+            CodecError, DecodeOptions, PacketBuffer, PacketRead, PacketWrite, encode_packet,
+            write_varint,
+        };
+        use bytes::{BufMut, Bytes, BytesMut};
+
+        let update = |network_id, components: Option<&'static [u8]>| EntityUpdate {
+            network_id,
+            removed_components: vec![1],
+            updated_components: components.map(|data| ComponentUpdates {
+                count: 2,
+                data: Bytes::from_static(data),
+                following: 0,
+            }),
+        };
+        // As the server sends it, three entities, the second with component updates
+        let mut entries = BytesMut::new();
+        update(1, None).write(&mut entries);
+        update(2, Some(&[0xC0, 0xFF, 0xEE, 0, 0, 0, 7])).write(&mut entries);
+        let third = entries.len();
+        update(3, None).write(&mut entries);
+        let mut payload = BytesMut::new();
+        payload.put_u8(2);
+        payload.put_i32_le(-1);
+        payload.put_i32_le(0);
+        write_varint(&mut payload, 3);
+        payload.put_slice(&entries);
+
+        let mut buf = PacketBuffer::with_options(payload.clone().freeze(), DecodeOptions::STRICT);
+        let updates = EntityUpdates::read(&mut buf).unwrap();
+        buf.finish().unwrap();
+        assert_eq!(updates.updates.len(), 2);
+        assert_eq!(updates.updates[0].network_id, 1);
+        assert!(updates.updates[0].updated_components.is_none());
+        assert_eq!(updates.updates[1].network_id, 2);
+        assert_eq!(updates.updates[1].removed_components, [1]);
+        let components = updates.updates[1].updated_components.as_ref().unwrap();
+        assert_eq!(components.count, 2);
+        assert_eq!(components.following, 1);
+        assert_eq!(
+            components.data,
+            [&[0xC0, 0xFF, 0xEE, 0, 0, 0, 7][..], &entries[third..]].concat()
+        );
+
+        // Written back byte for byte
+        let mut written = BytesMut::new();
+        updates.write(&mut written);
+        assert_eq!(written, payload);
+        round_trip(&updates);
+
+        // A `following` the data can't hold would corrupt the list length
+        let mut broken = updates.clone();
+        broken.updates[1]
+            .updated_components
+            .as_mut()
+            .unwrap()
+            .following = 100;
+        assert!(matches!(encode_packet(&broken), Err(CodecError::Other(_))));
+        // and only the last update can carry the ones that follow
+        let mut broken = updates.clone();
+        broken.updates.swap(0, 1);
+        assert!(matches!(encode_packet(&broken), Err(CodecError::Other(_))));
+    }
+
+    #[test]
+    fn velocity() {
+        let knockback = round_trip(&ApplyKnockback {
+            entity_id: 7,
+            velocity_x: 1.0,
+            velocity_y: 0.5,
+            velocity_z: -1.0,
+        });
+        assert_eq!(knockback.entity_id, 7);
+        assert_eq!(
+            (
+                knockback.velocity_x,
+                knockback.velocity_y,
+                knockback.velocity_z
+            ),
+            (1.0, 0.5, -1.0)
+        );
+        let velocity = round_trip(&ChangeVelocity {
+            entity_id: 8,
+            velocity_x: 0.0,
+            velocity_y: 2.0,
+            velocity_z: 0.0,
+        });
+        assert_eq!((velocity.entity_id, velocity.velocity_y), (8, 2.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn entity_updates_json() {
+        let json = crate::packets::json_round_trip(&EntityUpdates {
+            removed_entities: vec![1, 2, 3],
+            updates: vec![EntityUpdate {
+                network_id: 10,
+                removed_components: vec![4, 5],
+                updated_components: Some(ComponentUpdates {
+                    count: 2,
+                    data: bytes::Bytes::from_static(&[6, 7, 8]),
+                    following: 0,
+                }),
+            }],
+        });
+        assert_eq!(json["removed_entities"], serde_json::json!([1, 2, 3]));
+        assert_eq!(json["updates"][0]["network_id"], 10);
+        assert_eq!(
+            json["updates"][0]["updated_components"]["data"],
+            serde_json::json!([6, 7, 8])
+        );
+    }
+}
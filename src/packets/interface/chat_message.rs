@@ -1,7 +1,11 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_string};
+use crate::codec::{
+    CodecResult, MAX_VAR_STRING_SIZE, Packet, PacketBuffer, PacketRead, PacketWrite, write_string,
+};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ChatType {
     Normal = 0,
@@ -9,7 +13,8 @@ pub enum ChatType {
     Whisper = 2,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChatMessage {
     pub message: String,
     pub chat_type: ChatType,
@@ -18,20 +23,23 @@ pub struct ChatMessage {
 
 impl Packet for ChatMessage {
     const PACKET_ID: u32 = 99;
+    const DIRECTION: PacketDirection = PacketDirection::Bidirectional;
+    const MAX_SIZE: usize = 2 + 2 * MAX_VAR_STRING_SIZE;
 }
 
 impl PacketRead for ChatMessage {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
-        let chat_type = match buf.read_u8()? {
-            1 => ChatType::System,
-            2 => ChatType::Whisper,
-            _ => ChatType::Normal,
-        };
+        let chat_type = buf.field("chat_type", |buf| match buf.read_u8()? {
+            0 => Ok(ChatType::Normal),
+            1 => Ok(ChatType::System),
+            2 => Ok(ChatType::Whisper),
+            other => buf.unknown_variant("ChatType", other as i32, ChatType::Normal),
+        })?;
 
-        let message = buf.read_var_string()?;
+        let message = buf.field("message", |buf| buf.read_var_string())?;
         let sender_name = if null_bits & 1 != 0 {
-            Some(buf.read_var_string()?)
+            Some(buf.field("sender_name", |buf| buf.read_var_string())?)
         } else {
             None
         };
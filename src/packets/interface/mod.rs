@@ -5,3 +5,85 @@ pub mod server_info;
 pub use block_change::*;
 pub use chat_message::*;
 pub use server_info::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::round_trip;
+
+    #[test]
+    fn chat_message() {
+        let message = round_trip(&ChatMessage {
+            message: "héllo wörld".to_string(),
+            chat_type: ChatType::Whisper,
+            sender_name: Some("Player".to_string()),
+        });
+        assert_eq!(message.message, "héllo wörld");
+        assert_eq!(message.chat_type, ChatType::Whisper);
+        assert_eq!(message.sender_name.as_deref(), Some("Player"));
+
+        let message = round_trip(&ChatMessage {
+            message: String::new(),
+            chat_type: ChatType::System,
+            sender_name: None,
+        });
+        assert_eq!(message.sender_name, None);
+    }
+
+    #[test]
+    fn truncated_chat_message() {
+        use crate::codec::{CodecError, Packet, decode_payload, encode_packet};
+
+        let frame = encode_packet(&ChatMessage {
+            message: "hi".to_string(),
+            chat_type: ChatType::Normal,
+            sender_name: Some("Player".to_string()),
+        })
+        .unwrap();
+        let payload = frame.freeze().slice(8..);
+        let error = decode_payload::<ChatMessage>(payload.slice(..payload.len() - 2)).unwrap_err();
+        let CodecError::Decode(error) = error else {
+            panic!("{error:?}")
+        };
+        assert_eq!(error.packet, Some(("ChatMessage", ChatMessage::PACKET_ID)));
+        assert_eq!(error.path, ["sender_name"]);
+        assert_eq!(error.offset, 6);
+        assert!(matches!(error.source, CodecError::BufferUnderflow { .. }));
+    }
+
+    #[test]
+    fn block_change() {
+        let change = round_trip(&BlockChange {
+            x: -1,
+            y: 64,
+            z: i32::MAX,
+            block_state_id: 7,
+        });
+        assert_eq!((change.x, change.y, change.z), (-1, 64, i32::MAX));
+        assert_eq!(change.block_state_id, 7);
+    }
+
+    #[test]
+    fn server_info() {
+        let info = round_trip(&ServerInfo {
+            server_name: "Test Server".to_string(),
+            motd: Some("Welcome".to_string()),
+            max_players: 100,
+        });
+        assert_eq!(info.server_name, "Test Server");
+        assert_eq!(info.motd.as_deref(), Some("Welcome"));
+        assert_eq!(info.max_players, 100);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn chat_message_json() {
+        let json = crate::packets::json_round_trip(&ChatMessage {
+            message: "héllo wörld".to_string(),
+            chat_type: ChatType::Whisper,
+            sender_name: Some("Player".to_string()),
+        });
+        assert_eq!(json["message"], "héllo wörld");
+        assert_eq!(json["chat_type"], "Whisper");
+    }
+}
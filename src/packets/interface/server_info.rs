@@ -1,7 +1,11 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_string};
+use crate::codec::{
+    CodecResult, MAX_VAR_STRING_SIZE, Packet, PacketBuffer, PacketRead, PacketWrite, write_string,
+};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ServerInfo {
     pub server_name: String,
     pub motd: Option<String>,
@@ -10,16 +14,18 @@ pub struct ServerInfo {
 
 impl Packet for ServerInfo {
     const PACKET_ID: u32 = 223;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 5 + 2 * MAX_VAR_STRING_SIZE;
 }
 
 impl PacketRead for ServerInfo {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
-        let server_name = buf.read_var_string()?;
-        let max_players = buf.read_int_le()?;
+        let server_name = buf.field("server_name", |buf| buf.read_var_string())?;
+        let max_players = buf.field("max_players", |buf| buf.read_int_le())?;
 
         let motd = if null_bits & 1 != 0 {
-            Some(buf.read_var_string()?)
+            Some(buf.field("motd", |buf| buf.read_var_string())?)
         } else {
             None
         };
@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DropItemStack {
     pub slot: i32,
     pub count: i32,
@@ -9,13 +11,15 @@ pub struct DropItemStack {
 
 impl Packet for DropItemStack {
     const PACKET_ID: u32 = 234;
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const MAX_SIZE: usize = 8;
 }
 
 impl PacketRead for DropItemStack {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            slot: buf.read_int_le()?,
-            count: buf.read_int_le()?,
+            slot: buf.field("slot", |buf| buf.read_int_le())?,
+            count: buf.field("count", |buf| buf.read_int_le())?,
         })
     }
 }
@@ -5,3 +5,22 @@ pub mod set_active_slot;
 pub use drop_item_stack::*;
 pub use move_item_stack::*;
 pub use set_active_slot::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::round_trip;
+
+    #[test]
+    fn slots() {
+        assert_eq!(round_trip(&SetActiveSlot { slot: 8 }).slot, 8);
+        let drop = round_trip(&DropItemStack { slot: 3, count: 64 });
+        assert_eq!((drop.slot, drop.count), (3, 64));
+        let moved = round_trip(&MoveItemStack {
+            from_slot: 0,
+            to_slot: 35,
+            count: -1,
+        });
+        assert_eq!((moved.from_slot, moved.to_slot, moved.count), (0, 35, -1));
+    }
+}
@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MoveItemStack {
     pub from_slot: i32,
     pub to_slot: i32,
@@ -10,14 +12,16 @@ pub struct MoveItemStack {
 
 impl Packet for MoveItemStack {
     const PACKET_ID: u32 = 235;
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const MAX_SIZE: usize = 12;
 }
 
 impl PacketRead for MoveItemStack {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            from_slot: buf.read_int_le()?,
-            to_slot: buf.read_int_le()?,
-            count: buf.read_int_le()?,
+            from_slot: buf.field("from_slot", |buf| buf.read_int_le())?,
+            to_slot: buf.field("to_slot", |buf| buf.read_int_le())?,
+            count: buf.field("count", |buf| buf.read_int_le())?,
         })
     }
 }
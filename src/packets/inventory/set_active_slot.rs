@@ -1,19 +1,23 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetActiveSlot {
     pub slot: i32,
 }
 
 impl Packet for SetActiveSlot {
     const PACKET_ID: u32 = 233;
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const MAX_SIZE: usize = 4;
 }
 
 impl PacketRead for SetActiveSlot {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            slot: buf.read_int_le()?,
+            slot: buf.field("slot", |buf| buf.read_int_le())?,
         })
     }
 }
@@ -8,10 +8,85 @@ pub mod interaction;
 pub mod interface;
 pub mod inventory;
 pub mod machinima;
-pub mod play;
 pub mod player;
 pub mod serveraccess;
 pub mod setup;
 pub mod window;
 pub mod world;
 pub mod worldmap;
+
+/// Encode `packet`, decode it back strictly, both as `P` and through the
+/// direction's dispatch enum, and check the result encodes to the same frame.
+#[cfg(test)]
+pub(crate) fn round_trip<P: crate::codec::Packet>(packet: &P) -> P {
+    use crate::codec::{
+        DEFAULT_MAX_FRAME_LENGTH, DecodeOptions, ZSTD_MAGIC, decode_payload_with, encode_packet,
+        split_frame,
+    };
+    use crate::{
+        ConnectionState, PacketDirection, deserialize_client_bound_in,
+        deserialize_server_bound_with,
+    };
+
+    let frame = encode_packet(packet).unwrap();
+    let (packet_id, payload) = split_frame(&mut frame.clone(), DEFAULT_MAX_FRAME_LENGTH)
+        .unwrap()
+        .unwrap();
+    assert_eq!(packet_id, P::PACKET_ID);
+    if !payload.is_empty() {
+        assert_eq!(payload.starts_with(&ZSTD_MAGIC), P::IS_COMPRESSED);
+    }
+
+    let name = std::any::type_name::<P>().rsplit("::").next().unwrap();
+    let mut warnings = Vec::new();
+    if P::DIRECTION.overlaps(PacketDirection::Serverbound) {
+        let decoded = deserialize_server_bound_with(
+            packet_id,
+            payload.clone(),
+            DecodeOptions::STRICT,
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(decoded.name(), name);
+        assert_eq!(decoded.encode().unwrap(), frame);
+    }
+    if P::DIRECTION.overlaps(PacketDirection::Clientbound) {
+        // A phase the packet is sent in, which resolves the IDs shared by two packets
+        let state = [
+            ConnectionState::Handshake,
+            ConnectionState::Auth,
+            ConnectionState::Setup,
+            ConnectionState::Play,
+        ]
+        .into_iter()
+        .find(|state| state.packets().iter().any(|info| info.name == name))
+        .unwrap();
+        let decoded = deserialize_client_bound_in(
+            state,
+            packet_id,
+            payload.clone(),
+            DecodeOptions::STRICT,
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(decoded.name(), name);
+        assert_eq!(decoded.encode().unwrap(), frame);
+    }
+    assert!(warnings.is_empty(), "{warnings:?}");
+
+    let decoded: P = decode_payload_with(payload, DecodeOptions::STRICT, &mut warnings).unwrap();
+    assert_eq!(encode_packet(&decoded).unwrap(), frame);
+    decoded
+}
+
+/// Serialize `packet` to JSON and back, checking nothing is lost on the way.
+#[cfg(all(test, feature = "serde"))]
+pub(crate) fn json_round_trip<P>(packet: &P) -> serde_json::Value
+where
+    P: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let json = serde_json::to_value(packet).unwrap();
+    let decoded: P = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(&decoded, packet);
+    json
+}
@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClientMovement {
     pub position_x: f64,
     pub position_y: f64,
@@ -16,20 +18,22 @@ pub struct ClientMovement {
 
 impl Packet for ClientMovement {
     const PACKET_ID: u32 = 25;
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const MAX_SIZE: usize = 45;
 }
 
 impl PacketRead for ClientMovement {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            position_x: buf.read_f64()?,
-            position_y: buf.read_f64()?,
-            position_z: buf.read_f64()?,
-            velocity_x: buf.read_f32()?,
-            velocity_y: buf.read_f32()?,
-            velocity_z: buf.read_f32()?,
-            yaw: buf.read_f32()?,
-            pitch: buf.read_f32()?,
-            on_ground: buf.read_bool()?,
+            position_x: buf.field("position_x", |buf| buf.read_f64_le())?,
+            position_y: buf.field("position_y", |buf| buf.read_f64_le())?,
+            position_z: buf.field("position_z", |buf| buf.read_f64_le())?,
+            velocity_x: buf.field("velocity_x", |buf| buf.read_f32_le())?,
+            velocity_y: buf.field("velocity_y", |buf| buf.read_f32_le())?,
+            velocity_z: buf.field("velocity_z", |buf| buf.read_f32_le())?,
+            yaw: buf.field("yaw", |buf| buf.read_f32_le())?,
+            pitch: buf.field("pitch", |buf| buf.read_f32_le())?,
+            on_ground: buf.field("on_ground", |buf| buf.read_bool())?,
         })
     }
 }
@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClientTeleport {
     pub position_x: f64,
     pub position_y: f64,
@@ -12,16 +14,18 @@ pub struct ClientTeleport {
 
 impl Packet for ClientTeleport {
     const PACKET_ID: u32 = 26;
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const MAX_SIZE: usize = 32;
 }
 
 impl PacketRead for ClientTeleport {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            position_x: buf.read_f64()?,
-            position_y: buf.read_f64()?,
-            position_z: buf.read_f64()?,
-            yaw: buf.read_f32()?,
-            pitch: buf.read_f32()?,
+            position_x: buf.field("position_x", |buf| buf.read_f64_le())?,
+            position_y: buf.field("position_y", |buf| buf.read_f64_le())?,
+            position_z: buf.field("position_z", |buf| buf.read_f64_le())?,
+            yaw: buf.field("yaw", |buf| buf.read_f32_le())?,
+            pitch: buf.field("pitch", |buf| buf.read_f32_le())?,
         })
     }
 }
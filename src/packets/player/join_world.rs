@@ -1,8 +1,10 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_uuid};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JoinWorld {
     pub clear_world: bool,
     pub fade_in_out: bool,
@@ -11,16 +13,16 @@ pub struct JoinWorld {
 
 impl Packet for JoinWorld {
     const PACKET_ID: u32 = 104;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 18;
 }
 
 impl PacketRead for JoinWorld {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            clear_world: buf.read_bool()?,
-            fade_in_out: buf.read_bool()?,
-            world_uuid: Uuid::from_u128(
-                ((buf.read_u64()? as u128) << 64) | (buf.read_u64()? as u128),
-            ),
+            clear_world: buf.field("clear_world", |buf| buf.read_bool())?,
+            fade_in_out: buf.field("fade_in_out", |buf| buf.read_bool())?,
+            world_uuid: buf.field("world_uuid", |buf| buf.read_uuid_be())?,
         })
     }
 }
@@ -29,7 +31,6 @@ impl PacketWrite for JoinWorld {
     fn write(&self, buf: &mut BytesMut) {
         buf.put_u8(if self.clear_world { 1 } else { 0 });
         buf.put_u8(if self.fade_in_out { 1 } else { 0 });
-        buf.put_u64((self.world_uuid.as_u128() >> 64) as u64);
-        buf.put_u64(self.world_uuid.as_u128() as u64);
+        write_uuid(buf, &self.world_uuid);
     }
 }
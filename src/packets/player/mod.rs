@@ -7,3 +7,53 @@ pub use client_movement::*;
 pub use client_ready::*;
 pub use client_teleport::*;
 pub use join_world::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::round_trip;
+    use uuid::Uuid;
+
+    #[test]
+    fn client_movement() {
+        let movement = round_trip(&ClientMovement {
+            position_x: 1.5,
+            position_y: -64.25,
+            position_z: 1e9,
+            velocity_x: 0.1,
+            velocity_y: -9.8,
+            velocity_z: 0.0,
+            yaw: 90.0,
+            pitch: -45.0,
+            on_ground: true,
+        });
+        assert_eq!(movement.position_y, -64.25);
+        assert_eq!(movement.velocity_y, -9.8);
+        assert!(movement.on_ground);
+    }
+
+    #[test]
+    fn client_teleport() {
+        let teleport = round_trip(&ClientTeleport {
+            position_x: -3.0,
+            position_y: 120.0,
+            position_z: 7.75,
+            yaw: 180.0,
+            pitch: 10.0,
+        });
+        assert_eq!(teleport.position_z, 7.75);
+        assert_eq!(teleport.yaw, 180.0);
+    }
+
+    #[test]
+    fn client_ready_and_join_world() {
+        round_trip(&ClientReady);
+        let join = round_trip(&JoinWorld {
+            clear_world: true,
+            fade_in_out: false,
+            world_uuid: Uuid::from_u128(42),
+        });
+        assert!(join.clear_world && !join.fade_in_out);
+        assert_eq!(join.world_uuid, Uuid::from_u128(42));
+    }
+}
@@ -1,8 +1,12 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::codec::{
+    CodecResult, MAX_VAR_STRING_SIZE, Packet, PacketBuffer, PacketRead, PacketWrite,
+};
 use crate::common::Asset;
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssetInitialize {
     pub asset: Asset,
     pub size: i32,
@@ -10,12 +14,14 @@ pub struct AssetInitialize {
 
 impl Packet for AssetInitialize {
     const PACKET_ID: u32 = 24;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 4 + 64 + MAX_VAR_STRING_SIZE;
 }
 
 impl PacketRead for AssetInitialize {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
-        let size = buf.read_int_le()?;
-        let asset = Asset::read(buf)?;
+        let size = buf.field("size", |buf| buf.read_int_le())?;
+        let asset = buf.field("asset", Asset::read)?;
 
         Ok(AssetInitialize { asset, size })
     }
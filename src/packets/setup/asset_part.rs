@@ -1,27 +1,28 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
-use bytes::{BufMut, BytesMut};
+use crate::packet::PacketDirection;
+use bytes::{BufMut, Bytes, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssetPart {
-    pub part: Option<Vec<u8>>,
+    pub part: Option<Bytes>,
 }
 
 impl Packet for AssetPart {
     const PACKET_ID: u32 = 25;
-    fn is_compressed() -> bool {
-        true
-    }
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const IS_COMPRESSED: bool = true;
+    const MAX_SIZE: usize = 1 + 5 + 4_096_000;
 }
 
 impl PacketRead for AssetPart {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_byte()?;
         let part = if (null_bits & 1) != 0 {
-            let len = buf.read_var_int()? as usize;
-            if len > 4_096_000 {
-                return Err(crate::codec::CodecError::Other("Part too long".to_string()));
-            }
-            Some(buf.read_bytes(len)?.to_vec())
+            Some(buf.field("part", |buf| {
+                let len = buf.read_count(1)?;
+                buf.read_bytes(len)
+            })?)
         } else {
             None
         };
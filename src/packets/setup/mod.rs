@@ -29,3 +29,151 @@ pub use view_radius::*;
 pub use world_load_finished::*;
 pub use world_load_progress::*;
 pub use world_settings::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Asset;
+    use crate::packets::round_trip;
+    use bytes::Bytes;
+
+    fn asset(name: &str) -> Asset {
+        Asset {
+            hash: format!("{name:0>64}"),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn world_settings() {
+        let settings = round_trip(&WorldSettings {
+            game_version: "2026.01.13".to_string(),
+            world_seed: -42,
+            time_of_day: 0.25,
+            weather_seed: 7,
+            world_height: 320,
+            required_assets: Some(vec![asset("Common/Blocks.json"), asset("Sky.png")]),
+        });
+        assert_eq!(settings.game_version, "2026.01.13");
+        assert_eq!((settings.world_seed, settings.weather_seed), (-42, 7));
+        assert_eq!(settings.time_of_day, 0.25);
+        assert_eq!(settings.world_height, 320);
+        assert_eq!(
+            settings.required_assets,
+            Some(vec![asset("Common/Blocks.json"), asset("Sky.png")])
+        );
+    }
+
+    #[test]
+    fn truncated_world_settings() {
+        use crate::codec::{CodecError, DecodeOptions, Packet, PacketWrite, read_packet};
+        use bytes::BytesMut;
+
+        let settings = WorldSettings {
+            game_version: "2026.01.13".to_string(),
+            world_seed: 0,
+            time_of_day: 0.0,
+            weather_seed: 0,
+            world_height: 320,
+            required_assets: Some(vec![asset("Sky.png")]),
+        };
+        let mut payload = BytesMut::new();
+        settings.write(&mut payload);
+        let payload = payload.freeze();
+        // Compressed on the wire, so cut the decompressed payload
+        let error = read_packet::<WorldSettings>(
+            payload.slice(..payload.len() - 1),
+            DecodeOptions::STRICT,
+            &mut Vec::new(),
+        )
+        .unwrap_err();
+        let CodecError::Decode(error) = error else {
+            panic!("{error:?}")
+        };
+        assert_eq!(
+            error.packet,
+            Some(("WorldSettings", WorldSettings::PACKET_ID))
+        );
+        assert_eq!(error.path, ["required_assets", "name"]);
+        // Stopped right after the name's length
+        assert_eq!(error.offset, payload.len() - "Sky.png".len());
+    }
+
+    #[test]
+    fn world_loading() {
+        let progress = round_trip(&WorldLoadProgress {
+            percent_complete: 50,
+            percent_complete_subitem: 3,
+            status: Some("Loading chunks".to_string()),
+        });
+        assert_eq!(
+            (progress.percent_complete, progress.percent_complete_subitem),
+            (50, 3)
+        );
+        assert_eq!(progress.status.as_deref(), Some("Loading chunks"));
+        round_trip(&WorldLoadFinished {});
+    }
+
+    #[test]
+    fn assets() {
+        let request = round_trip(&RequestAssets {
+            assets: Some(vec![asset("a.png"), asset("b.json")]),
+        });
+        assert_eq!(request.assets, Some(vec![asset("a.png"), asset("b.json")]));
+        assert_eq!(round_trip(&RequestAssets { assets: None }).assets, None);
+
+        let initialize = round_trip(&AssetInitialize {
+            asset: asset("a.png"),
+            size: 100_000,
+        });
+        assert_eq!(initialize.asset, asset("a.png"));
+        assert_eq!(initialize.size, 100_000);
+
+        let data: Bytes = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let part = round_trip(&AssetPart {
+            part: Some(data.clone()),
+        });
+        assert_eq!(part.part, Some(data));
+        assert_eq!(round_trip(&AssetPart { part: None }).part, None);
+        round_trip(&AssetFinalize);
+        round_trip(&RequestCommonAssetsRebuild {});
+    }
+
+    #[test]
+    fn player_options() {
+        let options = round_trip(&PlayerOptions {
+            skin: Some(PlayerSkin {
+                face: Some("Face_1".to_string()),
+                haircut: Some("Haircut_Long".to_string()),
+                cape: Some("Cape_Red".to_string()),
+                ..PlayerSkin::default()
+            }),
+        });
+        let skin = options.skin.unwrap();
+        assert_eq!(skin.face.as_deref(), Some("Face_1"));
+        assert_eq!(skin.haircut.as_deref(), Some("Haircut_Long"));
+        assert_eq!(skin.cape.as_deref(), Some("Cape_Red"));
+        assert_eq!(skin.eyes, None);
+        assert!(round_trip(&PlayerOptions { skin: None }).skin.is_none());
+    }
+
+    #[test]
+    fn settings() {
+        let rate = round_trip(&SetUpdateRate {
+            updates_per_second: 30.0,
+        });
+        assert_eq!(rate.updates_per_second, 30.0);
+        let dilation = round_trip(&SetTimeDilation { time_dilation: 0.5 });
+        assert_eq!(dilation.time_dilation, 0.5);
+        assert_eq!(round_trip(&ViewRadius { value: 12 }).value, 12);
+        assert_eq!(round_trip(&SetClientId { client_id: 3 }).client_id, 3);
+        assert_eq!(
+            round_trip(&SetEntitySeed { entity_seed: -9 }).entity_seed,
+            -9
+        );
+        let features = round_trip(&UpdateFeatures {
+            features: Some(vec![(0, true), (4, false)]),
+        });
+        assert_eq!(features.features, Some(vec![(0, true), (4, false)]));
+    }
+}
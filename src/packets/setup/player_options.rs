@@ -1,243 +1,72 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_string};
+use crate::HytalePacket;
+use crate::codec::{
+    CodecResult, MAX_VAR_STRING_SIZE, Packet, PacketBuffer, PacketRead, PacketWrite,
+};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, HytalePacket)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlayerSkin {
+    #[hytale(nullable, offset, var_string)]
     pub body_characteristic: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub underwear: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub face: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub eyes: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub ears: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub mouth: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub facial_hair: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub haircut: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub eyebrows: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub pants: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub overpants: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub undertop: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub overtop: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub shoes: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub head_accessory: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub face_accessory: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub ear_accessory: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub skin_feature: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub gloves: Option<String>,
+    #[hytale(nullable, offset, var_string)]
     pub cape: Option<String>,
 }
 
-impl PlayerSkin {
-    pub fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
-        let null_bits = buf.read_bytes(3)?;
-
-        // Skip offset table (20 fields * 4 bytes)
-        for _ in 0..20 {
-            buf.read_int_le()?;
-        }
-
-        let mut skin = PlayerSkin::default();
-        let has_bit =
-            |byte_idx: usize, bit_mask: u8| -> bool { (null_bits[byte_idx] & bit_mask) != 0 };
-
-        if has_bit(0, 1) {
-            skin.body_characteristic = Some(buf.read_var_string()?);
-        }
-        if has_bit(0, 2) {
-            skin.underwear = Some(buf.read_var_string()?);
-        }
-        if has_bit(0, 4) {
-            skin.face = Some(buf.read_var_string()?);
-        }
-        if has_bit(0, 8) {
-            skin.eyes = Some(buf.read_var_string()?);
-        }
-        if has_bit(0, 16) {
-            skin.ears = Some(buf.read_var_string()?);
-        }
-        if has_bit(0, 32) {
-            skin.mouth = Some(buf.read_var_string()?);
-        }
-        if has_bit(0, 64) {
-            skin.facial_hair = Some(buf.read_var_string()?);
-        }
-        if has_bit(0, 128) {
-            skin.haircut = Some(buf.read_var_string()?);
-        }
-
-        if has_bit(1, 1) {
-            skin.eyebrows = Some(buf.read_var_string()?);
-        }
-        if has_bit(1, 2) {
-            skin.pants = Some(buf.read_var_string()?);
-        }
-        if has_bit(1, 4) {
-            skin.overpants = Some(buf.read_var_string()?);
-        }
-        if has_bit(1, 8) {
-            skin.undertop = Some(buf.read_var_string()?);
-        }
-        if has_bit(1, 16) {
-            skin.overtop = Some(buf.read_var_string()?);
-        }
-        if has_bit(1, 32) {
-            skin.shoes = Some(buf.read_var_string()?);
-        }
-        if has_bit(1, 64) {
-            skin.head_accessory = Some(buf.read_var_string()?);
-        }
-        if has_bit(1, 128) {
-            skin.face_accessory = Some(buf.read_var_string()?);
-        }
-
-        if has_bit(2, 1) {
-            skin.ear_accessory = Some(buf.read_var_string()?);
-        }
-        if has_bit(2, 2) {
-            skin.skin_feature = Some(buf.read_var_string()?);
-        }
-        if has_bit(2, 4) {
-            skin.gloves = Some(buf.read_var_string()?);
-        }
-        if has_bit(2, 8) {
-            skin.cape = Some(buf.read_var_string()?);
-        }
-
-        Ok(skin)
-    }
-
-    pub fn write(&self, buf: &mut BytesMut) {
-        let mut null_bits = [0u8; 3];
-        let mut fields = Vec::new();
-
-        if let Some(ref s) = self.body_characteristic {
-            null_bits[0] |= 1;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.underwear {
-            null_bits[0] |= 2;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.face {
-            null_bits[0] |= 4;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.eyes {
-            null_bits[0] |= 8;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.ears {
-            null_bits[0] |= 16;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.mouth {
-            null_bits[0] |= 32;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.facial_hair {
-            null_bits[0] |= 64;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.haircut {
-            null_bits[0] |= 128;
-            fields.push(s);
-        }
-
-        if let Some(ref s) = self.eyebrows {
-            null_bits[1] |= 1;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.pants {
-            null_bits[1] |= 2;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.overpants {
-            null_bits[1] |= 4;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.undertop {
-            null_bits[1] |= 8;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.overtop {
-            null_bits[1] |= 16;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.shoes {
-            null_bits[1] |= 32;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.head_accessory {
-            null_bits[1] |= 64;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.face_accessory {
-            null_bits[1] |= 128;
-            fields.push(s);
-        }
-
-        if let Some(ref s) = self.ear_accessory {
-            null_bits[2] |= 1;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.skin_feature {
-            null_bits[2] |= 2;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.gloves {
-            null_bits[2] |= 4;
-            fields.push(s);
-        }
-        if let Some(ref s) = self.cape {
-            null_bits[2] |= 8;
-            fields.push(s);
-        }
-
-        buf.put_slice(&null_bits);
-
-        let _var_block_start = 3 + 20 * 4;
-        let mut current_offset = 0i32;
-        let mut field_data = BytesMut::new();
-
-        let mut offsets = [0i32; 20];
-        let mut field_idx = 0;
-
-        // TODO this is equite elaborate.
-
-        for i in 0..20 {
-            // Check if field i is present
-            let byte_idx = i / 8;
-            let bit_mask = 1 << (i % 8);
-            if (null_bits[byte_idx] & bit_mask) != 0 {
-                offsets[i] = current_offset;
-                let s = fields[field_idx];
-                write_string(&mut field_data, s);
-                current_offset = field_data.len() as i32;
-                field_idx += 1;
-            } else {
-                offsets[i] = -1;
-            }
-        }
-
-        for offset in offsets {
-            buf.put_i32_le(offset);
-        }
-        buf.put_slice(&field_data);
-    }
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlayerOptions {
     pub skin: Option<PlayerSkin>,
 }
 
 impl Packet for PlayerOptions {
     const PACKET_ID: u32 = 33;
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const MAX_SIZE: usize = 1 + 3 + 20 * 4 + 20 * MAX_VAR_STRING_SIZE;
 }
 
 impl PacketRead for PlayerOptions {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
         let skin = if (null_bits & 1) != 0 {
-            Some(PlayerSkin::read(buf)?)
+            Some(buf.field("skin", PlayerSkin::read)?)
         } else {
             None
         };
@@ -1,28 +1,33 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_varint};
 use crate::common::Asset;
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RequestAssets {
     pub assets: Option<Vec<Asset>>,
 }
 
 impl Packet for RequestAssets {
     const PACKET_ID: u32 = 17;
-    fn is_compressed() -> bool {
-        true
-    }
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const IS_COMPRESSED: bool = true;
+    const MAX_SIZE: usize = 16 * 1024 * 1024;
 }
 
 impl PacketRead for RequestAssets {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
         if (null_bits & 1) != 0 {
-            let count = buf.read_varint()? as usize;
-            let mut assets = Vec::with_capacity(count);
-            for _ in 0..count {
-                assets.push(Asset::read(buf)?);
-            }
+            let assets = buf.field("assets", |buf| {
+                let count = buf.read_count(Asset::MIN_SIZE)?;
+                let mut assets = Vec::with_capacity(count);
+                for _ in 0..count {
+                    assets.push(Asset::read(buf)?);
+                }
+                Ok(assets)
+            })?;
             Ok(Self {
                 assets: Some(assets),
             })
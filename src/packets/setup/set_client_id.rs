@@ -1,19 +1,23 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetClientId {
     pub client_id: i32,
 }
 
 impl Packet for SetClientId {
     const PACKET_ID: u32 = 100;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 4;
 }
 
 impl PacketRead for SetClientId {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(SetClientId {
-            client_id: buf.read_int_le()?,
+            client_id: buf.field("client_id", |buf| buf.read_int_le())?,
         })
     }
 }
@@ -1,19 +1,23 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetEntitySeed {
     pub entity_seed: i32,
 }
 
 impl Packet for SetEntitySeed {
     const PACKET_ID: u32 = 160;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 4;
 }
 
 impl PacketRead for SetEntitySeed {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(SetEntitySeed {
-            entity_seed: buf.read_int_le()?,
+            entity_seed: buf.field("entity_seed", |buf| buf.read_int_le())?,
         })
     }
 }
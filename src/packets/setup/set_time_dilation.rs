@@ -1,19 +1,23 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetTimeDilation {
     pub time_dilation: f32,
 }
 
 impl Packet for SetTimeDilation {
     const PACKET_ID: u32 = 30;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 4;
 }
 
 impl PacketRead for SetTimeDilation {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            time_dilation: buf.read_f32()?,
+            time_dilation: buf.field("time_dilation", |buf| buf.read_f32_le())?,
         })
     }
 }
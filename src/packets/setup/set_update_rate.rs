@@ -1,19 +1,23 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetUpdateRate {
     pub updates_per_second: f32,
 }
 
 impl Packet for SetUpdateRate {
     const PACKET_ID: u32 = 29;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 4;
 }
 
 impl PacketRead for SetUpdateRate {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            updates_per_second: buf.read_f32()?,
+            updates_per_second: buf.field("updates_per_second", |buf| buf.read_f32_le())?,
         })
     }
 }
@@ -1,26 +1,33 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_varint};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdateFeatures {
     pub features: Option<Vec<(u8, bool)>>,
 }
 
 impl Packet for UpdateFeatures {
     const PACKET_ID: u32 = 31;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 1 + 5 + 2 * 256;
 }
 
 impl PacketRead for UpdateFeatures {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
         if (null_bits & 1) != 0 {
-            let count = buf.read_varint()? as usize;
-            let mut features = Vec::with_capacity(count);
-            for _ in 0..count {
-                let feature = buf.read_u8()?;
-                let enabled = buf.read_bool()?;
-                features.push((feature, enabled));
-            }
+            let features = buf.field("features", |buf| {
+                let count = buf.read_count(2)?;
+                let mut features = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let feature = buf.field("feature", |buf| buf.read_u8())?;
+                    let enabled = buf.field("enabled", |buf| buf.read_bool())?;
+                    features.push((feature, enabled));
+                }
+                Ok(features)
+            })?;
             Ok(Self {
                 features: Some(features),
             })
@@ -1,19 +1,23 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ViewRadius {
     pub value: i32,
 }
 
 impl Packet for ViewRadius {
     const PACKET_ID: u32 = 32;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 4;
 }
 
 impl PacketRead for ViewRadius {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            value: buf.read_int_le()?,
+            value: buf.field("value", |buf| buf.read_int_le())?,
         })
     }
 }
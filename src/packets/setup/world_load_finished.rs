@@ -1,11 +1,15 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::BytesMut;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WorldLoadFinished {}
 
 impl Packet for WorldLoadFinished {
     const PACKET_ID: u32 = 22;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 0;
 }
 
 impl PacketRead for WorldLoadFinished {
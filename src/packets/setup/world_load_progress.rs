@@ -1,7 +1,11 @@
-use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_string};
+use crate::codec::{
+    CodecResult, MAX_VAR_STRING_SIZE, Packet, PacketBuffer, PacketRead, PacketWrite, write_string,
+};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WorldLoadProgress {
     pub percent_complete: i32,
     pub percent_complete_subitem: i32,
@@ -10,16 +14,19 @@ pub struct WorldLoadProgress {
 
 impl Packet for WorldLoadProgress {
     const PACKET_ID: u32 = 21;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 9 + MAX_VAR_STRING_SIZE;
 }
 
 impl PacketRead for WorldLoadProgress {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
-        let percent_complete = buf.read_int_le()?;
-        let percent_complete_subitem = buf.read_int_le()?;
+        let percent_complete = buf.field("percent_complete", |buf| buf.read_int_le())?;
+        let percent_complete_subitem =
+            buf.field("percent_complete_subitem", |buf| buf.read_int_le())?;
 
         let status = if (null_bits & 1) != 0 {
-            Some(buf.read_var_string()?)
+            Some(buf.field("status", |buf| buf.read_var_string())?)
         } else {
             None
         };
@@ -1,8 +1,10 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_string};
 use crate::common::Asset;
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WorldSettings {
     pub game_version: String,
     pub world_seed: i64,
@@ -14,26 +16,29 @@ pub struct WorldSettings {
 
 impl Packet for WorldSettings {
     const PACKET_ID: u32 = 21;
-    fn is_compressed() -> bool {
-        true
-    }
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const IS_COMPRESSED: bool = true;
+    const MAX_SIZE: usize = 50 * 1024 * 1024;
 }
 
 impl PacketRead for WorldSettings {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
-        let game_version = buf.read_var_string()?;
-        let time_of_day = buf.read_f64()?;
-        let world_seed = buf.read_i64()?;
-        let weather_seed = buf.read_i64()?;
-        let world_height = buf.read_int_le()?;
+        let game_version = buf.field("game_version", |buf| buf.read_var_string())?;
+        let time_of_day = buf.field("time_of_day", |buf| buf.read_f64_le())?;
+        let world_seed = buf.field("world_seed", |buf| buf.read_i64_le())?;
+        let weather_seed = buf.field("weather_seed", |buf| buf.read_i64_le())?;
+        let world_height = buf.field("world_height", |buf| buf.read_int_le())?;
 
         let required_assets = if (null_bits & 1) != 0 {
-            let count = buf.read_varint()? as usize;
-            let mut assets = Vec::with_capacity(count);
-            for _ in 0..count {
-                assets.push(Asset::read(buf)?);
-            }
+            let assets = buf.field("required_assets", |buf| {
+                let count = buf.read_count(Asset::MIN_SIZE)?;
+                let mut assets = Vec::with_capacity(count);
+                for _ in 0..count {
+                    assets.push(Asset::read(buf)?);
+                }
+                Ok(assets)
+            })?;
             Some(assets)
         } else {
             None
@@ -7,3 +7,62 @@ pub use play_sound_event_2d::*;
 pub use play_sound_event_3d::*;
 pub use server_set_block::*;
 pub use set_chunk::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::round_trip;
+    use bytes::Bytes;
+
+    #[test]
+    fn set_chunk() {
+        let data: Bytes = (0..32 * 32 * 32).map(|i| (i % 7) as u8).collect();
+        let chunk = round_trip(&SetChunk {
+            x: 3,
+            y: -2,
+            z: 1,
+            local_light: None,
+            global_light: Some(Bytes::from_static(&[15; 64])),
+            data: Some(data.clone()),
+        });
+        assert_eq!((chunk.x, chunk.y, chunk.z), (3, -2, 1));
+        assert_eq!(chunk.local_light, None);
+        assert_eq!(chunk.global_light.as_deref(), Some(&[15; 64][..]));
+        assert_eq!(chunk.data, Some(data));
+    }
+
+    #[test]
+    fn server_set_block() {
+        let block = round_trip(&ServerSetBlock {
+            x: 10,
+            y: 20,
+            z: 30,
+            block_state_id: 2,
+        });
+        assert_eq!((block.x, block.y, block.z), (10, 20, 30));
+        assert_eq!(block.block_state_id, 2);
+    }
+
+    #[test]
+    fn sound_events() {
+        let sound = round_trip(&PlaySoundEvent2D {
+            sound_event_id: 5,
+            volume: 0.5,
+            pitch: 1.25,
+        });
+        assert_eq!(
+            (sound.sound_event_id, sound.volume, sound.pitch),
+            (5, 0.5, 1.25)
+        );
+        let sound = round_trip(&PlaySoundEvent3D {
+            sound_event_id: 6,
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            volume: 1.0,
+            pitch: 0.75,
+        });
+        assert_eq!((sound.x, sound.y, sound.z), (1.0, 2.0, 3.0));
+        assert_eq!(sound.pitch, 0.75);
+    }
+}
@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlaySoundEvent2D {
     pub sound_event_id: i32,
     pub volume: f32,
@@ -10,14 +12,16 @@ pub struct PlaySoundEvent2D {
 
 impl Packet for PlaySoundEvent2D {
     const PACKET_ID: u32 = 151;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 12;
 }
 
 impl PacketRead for PlaySoundEvent2D {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            sound_event_id: buf.read_int_le()?,
-            volume: buf.read_f32()?,
-            pitch: buf.read_f32()?,
+            sound_event_id: buf.field("sound_event_id", |buf| buf.read_int_le())?,
+            volume: buf.field("volume", |buf| buf.read_f32_le())?,
+            pitch: buf.field("pitch", |buf| buf.read_f32_le())?,
         })
     }
 }
@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlaySoundEvent3D {
     pub sound_event_id: i32,
     pub x: f32,
@@ -13,17 +15,19 @@ pub struct PlaySoundEvent3D {
 
 impl Packet for PlaySoundEvent3D {
     const PACKET_ID: u32 = 152;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 24;
 }
 
 impl PacketRead for PlaySoundEvent3D {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            sound_event_id: buf.read_int_le()?,
-            x: buf.read_f32()?,
-            y: buf.read_f32()?,
-            z: buf.read_f32()?,
-            volume: buf.read_f32()?,
-            pitch: buf.read_f32()?,
+            sound_event_id: buf.field("sound_event_id", |buf| buf.read_int_le())?,
+            x: buf.field("x", |buf| buf.read_f32_le())?,
+            y: buf.field("y", |buf| buf.read_f32_le())?,
+            z: buf.field("z", |buf| buf.read_f32_le())?,
+            volume: buf.field("volume", |buf| buf.read_f32_le())?,
+            pitch: buf.field("pitch", |buf| buf.read_f32_le())?,
         })
     }
 }
@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ServerSetBlock {
     pub x: i32,
     pub y: i32,
@@ -11,15 +13,17 @@ pub struct ServerSetBlock {
 
 impl Packet for ServerSetBlock {
     const PACKET_ID: u32 = 150;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 16;
 }
 
 impl PacketRead for ServerSetBlock {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         Ok(Self {
-            x: buf.read_int_le()?,
-            y: buf.read_int_le()?,
-            z: buf.read_int_le()?,
-            block_state_id: buf.read_int_le()?,
+            x: buf.field("x", |buf| buf.read_int_le())?,
+            y: buf.field("y", |buf| buf.read_int_le())?,
+            z: buf.field("z", |buf| buf.read_int_le())?,
+            block_state_id: buf.field("block_state_id", |buf| buf.read_int_le())?,
         })
     }
 }
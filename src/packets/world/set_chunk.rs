@@ -1,51 +1,55 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite, write_varint};
-use bytes::{BufMut, BytesMut};
+use crate::packet::PacketDirection;
+use bytes::{BufMut, Bytes, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetChunk {
     pub x: i32,
     pub y: i32,
     pub z: i32,
-    pub local_light: Option<Vec<u8>>,
-    pub global_light: Option<Vec<u8>>,
-    pub data: Option<Vec<u8>>,
+    pub local_light: Option<Bytes>,
+    pub global_light: Option<Bytes>,
+    pub data: Option<Bytes>,
 }
 
 impl Packet for SetChunk {
     const PACKET_ID: u32 = 131;
-
-    fn is_compressed() -> bool {
-        true
-    }
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const IS_COMPRESSED: bool = true;
+    const MAX_SIZE: usize = 13 + 3 * (5 + 4_096_000);
 }
 
 impl PacketRead for SetChunk {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let null_bits = buf.read_u8()?;
-        let x = buf.read_int_le()?;
-        let y = buf.read_int_le()?;
-        let z = buf.read_int_le()?;
+        let x = buf.field("x", |buf| buf.read_int_le())?;
+        let y = buf.field("y", |buf| buf.read_int_le())?;
+        let z = buf.field("z", |buf| buf.read_int_le())?;
 
         let local_light = if null_bits & 1 != 0 {
-            let len = buf.read_varint()? as usize;
-            let bytes = buf.read_bytes(len)?;
-            Some(bytes.to_vec())
+            Some(buf.field("local_light", |buf| {
+                let len = buf.read_count(1)?;
+                buf.read_bytes(len)
+            })?)
         } else {
             None
         };
 
         let global_light = if null_bits & 2 != 0 {
-            let len = buf.read_varint()? as usize;
-            let bytes = buf.read_bytes(len)?;
-            Some(bytes.to_vec())
+            Some(buf.field("global_light", |buf| {
+                let len = buf.read_count(1)?;
+                buf.read_bytes(len)
+            })?)
         } else {
             None
         };
 
         let data = if null_bits & 4 != 0 {
-            let len = buf.read_varint()? as usize;
-            let bytes = buf.read_bytes(len)?;
-            Some(bytes.to_vec())
+            Some(buf.field("data", |buf| {
+                let len = buf.read_count(1)?;
+                buf.read_bytes(len)
+            })?)
         } else {
             None
         };
@@ -3,3 +3,35 @@ pub mod update_world_map_settings;
 
 pub use update_world_map::*;
 pub use update_world_map_settings::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::round_trip;
+
+    #[test]
+    fn update_world_map_settings() {
+        let settings = round_trip(&UpdateWorldMapSettings {
+            enabled: true,
+            allow_coords: false,
+            allow_markers: true,
+            default_scale: 1.0,
+            min_scale: 0.25,
+            max_scale: 4.0,
+        });
+        assert!(settings.enabled && !settings.allow_coords && settings.allow_markers);
+        assert_eq!(
+            (
+                settings.default_scale,
+                settings.min_scale,
+                settings.max_scale
+            ),
+            (1.0, 0.25, 4.0)
+        );
+    }
+
+    #[test]
+    fn update_world_map() {
+        round_trip(&UpdateWorldMap {});
+    }
+}
@@ -1,21 +1,25 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdateWorldMap {
     // empty markers/chunks for now
 }
 
 impl Packet for UpdateWorldMap {
     const PACKET_ID: u32 = 241;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 50 * 1024 * 1024;
 }
 
 impl PacketRead for UpdateWorldMap {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let _null_bits = buf.read_u8()?;
-        buf.read_int_le()?; // chunksOffset
-        buf.read_int_le()?; // addedMarkersOffset
-        buf.read_int_le()?; // removedMarkersOffset
+        buf.field("chunks_offset", |buf| buf.read_int_le())?;
+        buf.field("added_markers_offset", |buf| buf.read_int_le())?;
+        buf.field("removed_markers_offset", |buf| buf.read_int_le())?;
         Ok(Self {})
     }
 }
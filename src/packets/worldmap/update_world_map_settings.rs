@@ -1,7 +1,9 @@
 use crate::codec::{CodecResult, Packet, PacketBuffer, PacketRead, PacketWrite};
+use crate::packet::PacketDirection;
 use bytes::{BufMut, BytesMut};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdateWorldMapSettings {
     pub enabled: bool,
     pub allow_coords: bool,
@@ -13,18 +15,20 @@ pub struct UpdateWorldMapSettings {
 
 impl Packet for UpdateWorldMapSettings {
     const PACKET_ID: u32 = 240;
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    const MAX_SIZE: usize = 16;
 }
 
 impl PacketRead for UpdateWorldMapSettings {
     fn read(buf: &mut PacketBuffer) -> CodecResult<Self> {
         let _null_bits = buf.read_u8()?; // biomeDataMap not supported yet
         Ok(Self {
-            enabled: buf.read_bool()?,
-            allow_coords: buf.read_bool()?,
-            allow_markers: buf.read_bool()?,
-            default_scale: buf.read_f32()?,
-            min_scale: buf.read_f32()?,
-            max_scale: buf.read_f32()?,
+            enabled: buf.field("enabled", |buf| buf.read_bool())?,
+            allow_coords: buf.field("allow_coords", |buf| buf.read_bool())?,
+            allow_markers: buf.field("allow_markers", |buf| buf.read_bool())?,
+            default_scale: buf.field("default_scale", |buf| buf.read_f32_le())?,
+            min_scale: buf.field("min_scale", |buf| buf.read_f32_le())?,
+            max_scale: buf.field("max_scale", |buf| buf.read_f32_le())?,
         })
     }
 }
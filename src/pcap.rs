@@ -0,0 +1,558 @@
+//! Offline import of Hytale traffic from pcap / pcapng files, e.g. from tcpdump.
+//!
+//! TCP streams to the server port are reassembled, split into frames and
+//! decoded with the packet enums, tracking the `ConnectionState` of each
+//! connection. A connection that fails to decode is recorded in
+//! `PcapTraffic::errors` and skipped, the others are still imported.
+//!
+//! Ethernet, Linux cooked (SLL / SLL2), BSD loopback and raw IP link layers
+//! are understood; IPv4 fragments and IPv6 extension headers are not.
+
+use crate::capture::CapturedPacket;
+use crate::codec::{
+    CodecError, CodecResult, DEFAULT_MAX_FRAME_LENGTH, DecodeOptions, DecodeWarning, split_frame,
+};
+use crate::packet::PacketDirection;
+use crate::state::ConnectionState;
+use bytes::{Bytes, BytesMut};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+
+/// Port Hytale servers listen on by default.
+pub const DEFAULT_PORT: u16 = 5520;
+
+/// A packet decoded from a capture file.
+#[derive(Debug, Clone)]
+pub struct PcapPacket {
+    /// Capture time of the segment that completed the frame, since the Unix epoch.
+    pub timestamp: Duration,
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub direction: PacketDirection,
+    /// Payload as it was on the wire, still compressed for compressed packets.
+    pub payload: Bytes,
+    /// Size of the payload once decompressed, `payload.len()` if it wasn't.
+    pub decompressed_size: usize,
+    pub packet: CapturedPacket,
+}
+
+/// A connection of a capture file that stopped decoding.
+#[derive(Debug)]
+pub struct PcapStreamError {
+    /// Capture time of the segment the error was found in, since the Unix epoch.
+    pub timestamp: Duration,
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    /// Direction of the frame that failed.
+    pub direction: PacketDirection,
+    pub error: CodecError,
+}
+
+/// Everything decoded from a capture file.
+#[derive(Debug, Default)]
+pub struct PcapTraffic {
+    /// Packets of every connection, in capture order.
+    pub packets: Vec<PcapPacket>,
+    /// Warnings recorded while decoding leniently.
+    pub warnings: Vec<DecodeWarning>,
+    /// Connections skipped from the frame that failed to decode on.
+    pub errors: Vec<PcapStreamError>,
+}
+
+/// Reads Hytale packets out of pcap / pcapng captures.
+///
+/// Connections whose SYN is not in the capture are skipped, since there is
+/// no way to tell where their frames start.
+#[derive(Debug, Clone)]
+pub struct PcapImporter {
+    server_port: u16,
+    max_frame_length: usize,
+    decode_options: DecodeOptions,
+}
+
+impl PcapImporter {
+    pub fn new() -> Self {
+        Self {
+            server_port: DEFAULT_PORT,
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            decode_options: DecodeOptions::default(),
+        }
+    }
+
+    /// TCP port of the server, used to tell the direction of each segment.
+    pub fn with_server_port(mut self, server_port: u16) -> Self {
+        self.server_port = server_port;
+        self
+    }
+
+    /// Reject frames whose payload is longer than `max_frame_length` bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    /// Decode packets strictly, or leniently while recording warnings.
+    pub fn with_decode_options(mut self, options: DecodeOptions) -> Self {
+        self.decode_options = options;
+        self
+    }
+
+    pub fn import_file(&self, path: impl AsRef<Path>) -> CodecResult<PcapTraffic> {
+        self.import(File::open(path)?)
+    }
+
+    /// Import a pcap or pcapng capture, telling them apart by their magic number.
+    pub fn import(&self, mut reader: impl Read) -> CodecResult<PcapTraffic> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let mut streams = Streams {
+            importer: self,
+            connections: HashMap::new(),
+            traffic: PcapTraffic::default(),
+        };
+        let mut on_packet = |timestamp, link_type, frame: &[u8]| {
+            if let Some(segment) = parse_link(link_type, frame).and_then(parse_ip) {
+                streams.push(timestamp, segment);
+            }
+            Ok(())
+        };
+        match data.get(..4) {
+            Some([0x0A, 0x0D, 0x0D, 0x0A]) => read_pcapng(&data, &mut on_packet)?,
+            _ => read_pcap(&data, &mut on_packet)?,
+        }
+        Ok(streams.traffic)
+    }
+}
+
+impl Default for PcapImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Import a capture file with the default options.
+pub fn import_pcap(path: impl AsRef<Path>) -> CodecResult<PcapTraffic> {
+    PcapImporter::new().import_file(path)
+}
+
+/// Whether `header`, the first bytes of a file, is a pcap or pcapng magic number.
+pub fn is_pcap(header: &[u8]) -> bool {
+    matches!(
+        header.get(..4),
+        Some(
+            [0x0A, 0x0D, 0x0D, 0x0A]
+                | [0xD4, 0xC3, 0xB2, 0xA1]
+                | [0xA1, 0xB2, 0xC3, 0xD4]
+                | [0x4D, 0x3C, 0xB2, 0xA1]
+                | [0xA1, 0xB2, 0x3C, 0x4D]
+        )
+    )
+}
+
+fn truncated() -> CodecError {
+    CodecError::Other("Truncated capture file".to_string())
+}
+
+/// Fixed size integers of a capture file, in its byte order.
+#[derive(Clone, Copy)]
+struct Endian {
+    big: bool,
+}
+
+impl Endian {
+    const LITTLE: Self = Self { big: false };
+    const BIG: Self = Self { big: true };
+
+    fn u16(self, data: &[u8], at: usize) -> CodecResult<u16> {
+        let bytes = data.get(at..at + 2).ok_or_else(truncated)?;
+        let bytes = [bytes[0], bytes[1]];
+        Ok(if self.big {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(self, data: &[u8], at: usize) -> CodecResult<u32> {
+        let bytes = data.get(at..at + 4).ok_or_else(truncated)?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Ok(if self.big {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
+
+type OnPacket<'a> = dyn FnMut(Duration, u32, &[u8]) -> CodecResult<()> + 'a;
+
+/// Classic libpcap format: a global header then `(ts_sec, ts_frac, caplen, len, data)` records.
+fn read_pcap(data: &[u8], on_packet: &mut OnPacket) -> CodecResult<()> {
+    let magic = Endian::LITTLE.u32(data, 0)?;
+    let (endian, nanos) = match magic {
+        0xA1B2C3D4 => (Endian::LITTLE, false),
+        0xD4C3B2A1 => (Endian::BIG, false),
+        0xA1B23C4D => (Endian::LITTLE, true),
+        0x4D3CB2A1 => (Endian::BIG, true),
+        _ => return Err(CodecError::Other("Not a pcap or pcapng file".to_string())),
+    };
+    let link_type = endian.u32(data, 20)?;
+
+    let mut at = 24;
+    while at < data.len() {
+        let seconds = endian.u32(data, at)? as u64;
+        let fraction = endian.u32(data, at + 4)?;
+        let length = endian.u32(data, at + 8)? as usize;
+        let frame = data.get(at + 16..at + 16 + length).ok_or_else(truncated)?;
+        let timestamp = if nanos {
+            Duration::new(seconds, 0) + Duration::from_nanos(fraction as u64)
+        } else {
+            Duration::new(seconds, 0) + Duration::from_micros(fraction as u64)
+        };
+        on_packet(timestamp, link_type, frame)?;
+        at += 16 + length;
+    }
+    Ok(())
+}
+
+/// Interface described by a pcapng Interface Description Block.
+struct Interface {
+    link_type: u32,
+    /// `if_tsresol`: `10^-n` seconds per tick, or `2^-n` if the high bit is set.
+    resolution: u8,
+}
+
+impl Interface {
+    fn timestamp(&self, ticks: u64) -> Duration {
+        let exponent = (self.resolution & 0x7F) as u32;
+        let nanos = if self.resolution & 0x80 != 0 {
+            (ticks as u128 * 1_000_000_000) >> exponent
+        } else if exponent <= 9 {
+            ticks as u128 * 10u128.pow(9 - exponent)
+        } else {
+            ticks as u128 / 10u128.checked_pow(exponent - 9).unwrap_or(u128::MAX)
+        };
+        Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+    }
+}
+
+/// pcapng: a sequence of sections, each with its own byte order and interfaces.
+fn read_pcapng(data: &[u8], on_packet: &mut OnPacket) -> CodecResult<()> {
+    const SECTION_HEADER: u32 = 0x0A0D0D0A;
+    const INTERFACE_DESCRIPTION: u32 = 1;
+    const SIMPLE_PACKET: u32 = 3;
+    const ENHANCED_PACKET: u32 = 6;
+
+    let mut endian = Endian::LITTLE;
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut at = 0;
+    while at < data.len() {
+        let block_type = endian.u32(data, at)?;
+        if block_type == SECTION_HEADER {
+            endian = match Endian::LITTLE.u32(data, at + 8)? {
+                0x1A2B3C4D => Endian::LITTLE,
+                0x4D3C2B1A => Endian::BIG,
+                _ => return Err(CodecError::Other("Invalid pcapng byte order".to_string())),
+            };
+            interfaces.clear();
+        }
+        let length = endian.u32(data, at + 4)? as usize;
+        if length < 12 {
+            return Err(CodecError::Other(format!(
+                "Invalid pcapng block length {length}"
+            )));
+        }
+        let body = data.get(at + 8..at + length - 4).ok_or_else(truncated)?;
+
+        match block_type {
+            INTERFACE_DESCRIPTION => {
+                let link_type = endian.u16(body, 0)? as u32;
+                let mut resolution = 6;
+                let mut option = 8;
+                while option + 4 <= body.len() {
+                    let code = endian.u16(body, option)?;
+                    let option_length = endian.u16(body, option + 2)? as usize;
+                    match code {
+                        0 => break,
+                        9 => resolution = *body.get(option + 4).ok_or_else(truncated)?,
+                        _ => {}
+                    }
+                    option += 4 + option_length.next_multiple_of(4);
+                }
+                interfaces.push(Interface {
+                    link_type,
+                    resolution,
+                });
+            }
+            ENHANCED_PACKET => {
+                let interface = interfaces
+                    .get(endian.u32(body, 0)? as usize)
+                    .ok_or_else(|| CodecError::Other("Unknown pcapng interface".to_string()))?;
+                let ticks = (endian.u32(body, 4)? as u64) << 32 | endian.u32(body, 8)? as u64;
+                let captured = endian.u32(body, 12)? as usize;
+                let frame = body.get(20..20 + captured).ok_or_else(truncated)?;
+                on_packet(interface.timestamp(ticks), interface.link_type, frame)?;
+            }
+            SIMPLE_PACKET => {
+                let interface = interfaces
+                    .first()
+                    .ok_or_else(|| CodecError::Other("Unknown pcapng interface".to_string()))?;
+                let captured = (endian.u32(body, 0)? as usize).min(body.len() - 4);
+                on_packet(Duration::ZERO, interface.link_type, &body[4..4 + captured])?;
+            }
+            _ => {}
+        }
+        at += length;
+    }
+    Ok(())
+}
+
+/// Strip the link layer, returning the IP packet.
+fn parse_link(link_type: u32, frame: &[u8]) -> Option<&[u8]> {
+    const NULL: u32 = 0;
+    const ETHERNET: u32 = 1;
+    const RAW: u32 = 101;
+    const LOOP: u32 = 108;
+    const LINUX_SLL: u32 = 113;
+    const IPV4: u32 = 228;
+    const IPV6: u32 = 229;
+    const LINUX_SLL2: u32 = 276;
+
+    match link_type {
+        // The address family doesn't matter, the IP version is in the header
+        NULL | LOOP => frame.get(4..),
+        ETHERNET => {
+            let mut at = 12;
+            // Skip VLAN tags
+            while matches!(frame.get(at..at + 2)?, [0x81, 0x00] | [0x88, 0xA8]) {
+                at += 4;
+            }
+            frame.get(at + 2..)
+        }
+        RAW | IPV4 | IPV6 => Some(frame),
+        LINUX_SLL => frame.get(16..),
+        LINUX_SLL2 => frame.get(20..),
+        _ => None,
+    }
+}
+
+/// A TCP segment.
+struct Segment<'a> {
+    source: SocketAddr,
+    destination: SocketAddr,
+    sequence: u32,
+    syn: bool,
+    payload: &'a [u8],
+}
+
+/// Parse an IPv4 or IPv6 packet carrying TCP.
+fn parse_ip(packet: &[u8]) -> Option<Segment<'_>> {
+    const TCP: u8 = 6;
+
+    let (source, destination, tcp) = match packet.first()? >> 4 {
+        4 => {
+            let header_length = (packet[0] & 0x0F) as usize * 4;
+            let total_length = u16::from_be_bytes([*packet.get(2)?, *packet.get(3)?]) as usize;
+            let fragment = u16::from_be_bytes([*packet.get(6)?, *packet.get(7)?]);
+            if *packet.get(9)? != TCP || fragment & 0x3FFF != 0 {
+                return None;
+            }
+            let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+            (
+                IpAddr::from(Ipv4Addr::from(source)),
+                IpAddr::from(Ipv4Addr::from(destination)),
+                // Ethernet pads short frames, trust the IP length
+                packet.get(header_length..total_length.min(packet.len()))?,
+            )
+        }
+        6 => {
+            let payload_length = u16::from_be_bytes([*packet.get(4)?, *packet.get(5)?]) as usize;
+            if *packet.get(6)? != TCP {
+                return None;
+            }
+            let source: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+            (
+                IpAddr::from(Ipv6Addr::from(source)),
+                IpAddr::from(Ipv6Addr::from(destination)),
+                packet.get(40..(40 + payload_length).min(packet.len()))?,
+            )
+        }
+        _ => return None,
+    };
+
+    let data_offset = (*tcp.get(12)? >> 4) as usize * 4;
+    Some(Segment {
+        source: SocketAddr::new(source, u16::from_be_bytes([tcp[0], tcp[1]])),
+        destination: SocketAddr::new(destination, u16::from_be_bytes([tcp[2], tcp[3]])),
+        sequence: u32::from_be_bytes(tcp.get(4..8)?.try_into().ok()?),
+        syn: tcp.get(13)? & 0x02 != 0,
+        payload: tcp.get(data_offset..)?,
+    })
+}
+
+/// One direction of a TCP connection, put back in sequence order.
+#[derive(Default)]
+struct HalfStream {
+    /// Sequence number of the next byte, unknown until the SYN is seen.
+    next: Option<u32>,
+    /// Bytes received in order that don't make a complete frame yet.
+    buffer: BytesMut,
+    /// Segments received ahead of `next`.
+    pending: Vec<(u32, Vec<u8>)>,
+}
+
+impl HalfStream {
+    fn push(&mut self, segment: &Segment) {
+        let mut sequence = segment.sequence;
+        if segment.syn {
+            sequence = sequence.wrapping_add(1);
+            self.next = Some(sequence);
+            self.buffer.clear();
+            self.pending.clear();
+        }
+        let Some(mut next) = self.next else {
+            return;
+        };
+        if !segment.payload.is_empty() {
+            self.pending.push((sequence, segment.payload.to_vec()));
+        }
+
+        // Sequence numbers wrap around, compare them as signed distances
+        while let Some(index) = self
+            .pending
+            .iter()
+            .position(|&(sequence, _)| sequence.wrapping_sub(next) as i32 <= 0)
+        {
+            let (sequence, data) = self.pending.swap_remove(index);
+            let seen = next.wrapping_sub(sequence) as usize;
+            // Retransmissions overlap what we already have
+            if seen < data.len() {
+                self.buffer.extend_from_slice(&data[seen..]);
+                next = next.wrapping_add((data.len() - seen) as u32);
+            }
+        }
+        self.next = Some(next);
+    }
+}
+
+struct Connection {
+    state: ConnectionState,
+    to_server: HalfStream,
+    to_client: HalfStream,
+    /// A frame failed to decode, the rest of the connection is skipped.
+    failed: bool,
+}
+
+/// The TCP connections of a capture, keyed by `(client, server)`.
+struct Streams<'a> {
+    importer: &'a PcapImporter,
+    connections: HashMap<(SocketAddr, SocketAddr), Connection>,
+    traffic: PcapTraffic,
+}
+
+impl Streams<'_> {
+    fn push(&mut self, timestamp: Duration, segment: Segment) {
+        let port = self.importer.server_port;
+        let (client, server, direction) = if segment.destination.port() == port {
+            (
+                segment.source,
+                segment.destination,
+                PacketDirection::Serverbound,
+            )
+        } else if segment.source.port() == port {
+            (
+                segment.destination,
+                segment.source,
+                PacketDirection::Clientbound,
+            )
+        } else {
+            return;
+        };
+
+        // A SYN from the client starts a new connection, even on a reused port
+        if segment.syn && direction == PacketDirection::Serverbound {
+            self.connections.insert(
+                (client, server),
+                Connection {
+                    state: ConnectionState::default(),
+                    to_server: HalfStream::default(),
+                    to_client: HalfStream::default(),
+                    failed: false,
+                },
+            );
+        }
+        let Some(connection) = self
+            .connections
+            .get_mut(&(client, server))
+            .filter(|connection| !connection.failed)
+        else {
+            return;
+        };
+
+        let half = match direction {
+            PacketDirection::Serverbound => &mut connection.to_server,
+            _ => &mut connection.to_client,
+        };
+        half.push(&segment);
+
+        let options = self.importer.decode_options;
+        let warnings = &mut self.traffic.warnings;
+        let result = (|| {
+            while let Some((packet_id, payload)) =
+                split_frame(&mut half.buffer, self.importer.max_frame_length)?
+            {
+                let (packet, decompressed_size) = match direction {
+                    PacketDirection::Serverbound => {
+                        let (packet, size) = connection.state.decode_server_bound_sized(
+                            packet_id,
+                            payload.clone(),
+                            options,
+                            warnings,
+                        )?;
+                        (CapturedPacket::ServerBound(packet), size)
+                    }
+                    _ => {
+                        let (packet, size) = connection.state.decode_client_bound_sized(
+                            packet_id,
+                            payload.clone(),
+                            options,
+                            warnings,
+                        )?;
+                        (CapturedPacket::ClientBound(packet), size)
+                    }
+                };
+                self.traffic.packets.push(PcapPacket {
+                    timestamp,
+                    client,
+                    server,
+                    direction,
+                    payload,
+                    decompressed_size,
+                    packet,
+                });
+            }
+            Ok(())
+        })();
+
+        if let Err(error) = result {
+            // The state can't be trusted anymore, drop what is buffered
+            connection.failed = true;
+            connection.to_server = HalfStream::default();
+            connection.to_client = HalfStream::default();
+            self.traffic.errors.push(PcapStreamError {
+                timestamp,
+                client,
+                server,
+                direction,
+                error,
+            });
+        }
+    }
+}
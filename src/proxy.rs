@@ -0,0 +1,333 @@
+//! Man-in-the-middle proxy between Hytale clients and an upstream server.
+//!
+//! Every frame is decoded into a `ServerBoundPacket` / `ClientBoundPacket` and
+//! handed to a `PacketFilter` that may rewrite or drop it. Only rewritten
+//! packets are re-encoded: unchanged ones, packets the crate doesn't model and
+//! frames that fail to decode go through byte for byte.
+
+use crate::client_bound::{ClientBoundPacket, deserialize_client_bound_in};
+use crate::codec::{
+    CodecError, CodecResult, DEFAULT_MAX_FRAME_LENGTH, DecodeOptions, DecodeWarning, FrameReader,
+    encode_raw_packet,
+};
+use crate::packet::PacketDirection;
+use crate::server_bound::{ServerBoundPacket, deserialize_server_bound_with};
+use crate::state::ConnectionState;
+use bytes::{Bytes, BytesMut};
+use std::io::{self, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Wait after the first of a run of failed accepts.
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(10);
+/// Longest wait between two accepts while they keep failing.
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// A client connection going through the proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyConnection {
+    /// Sequential number of the connection, starting at 0.
+    pub id: u64,
+    pub client: SocketAddr,
+    pub upstream: SocketAddr,
+}
+
+/// Hook called with every packet going through the proxy.
+///
+/// Return the packet, possibly rewritten, to forward it or `None` to drop it.
+/// The default methods forward everything unchanged.
+pub trait PacketFilter: Send + Sync + 'static {
+    fn server_bound(
+        &self,
+        connection: &ProxyConnection,
+        packet: ServerBoundPacket,
+    ) -> Option<ServerBoundPacket> {
+        let _ = connection;
+        Some(packet)
+    }
+
+    fn client_bound(
+        &self,
+        connection: &ProxyConnection,
+        packet: ClientBoundPacket,
+    ) -> Option<ClientBoundPacket> {
+        let _ = connection;
+        Some(packet)
+    }
+
+    /// Something unexpected that lenient decoding let through.
+    fn warning(&self, connection: &ProxyConnection, warning: DecodeWarning) {
+        let _ = (connection, warning);
+    }
+
+    /// A frame going in `direction` failed to decode and was forwarded as is.
+    fn undecodable(
+        &self,
+        connection: &ProxyConnection,
+        direction: PacketDirection,
+        packet_id: u32,
+        error: &CodecError,
+    ) {
+        let _ = (connection, direction, packet_id, error);
+    }
+
+    /// Accepting a client failed, the proxy keeps listening after a short wait.
+    fn accept_failed(&self, error: &io::Error) {
+        let _ = error;
+    }
+
+    /// The connection ended, with the error that ended it if any.
+    fn disconnected(&self, connection: &ProxyConnection, error: Option<&CodecError>) {
+        let _ = (connection, error);
+    }
+}
+
+/// Filter forwarding every packet unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PassThrough;
+
+impl PacketFilter for PassThrough {}
+
+/// Forwards client connections to `upstream` through a `PacketFilter`.
+///
+/// Uses blocking sockets and two threads per connection.
+pub struct Proxy<F> {
+    upstream: SocketAddr,
+    filter: Arc<F>,
+    max_frame_length: usize,
+    decode_options: DecodeOptions,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<F: PacketFilter> Proxy<F> {
+    pub fn new(upstream: SocketAddr, filter: F) -> Self {
+        Self {
+            upstream,
+            filter: Arc::new(filter),
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            decode_options: DecodeOptions::default(),
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Reject frames whose payload is longer than `max_frame_length` bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    /// Decode packets strictly, or leniently while reporting warnings to the filter.
+    pub fn with_decode_options(mut self, options: DecodeOptions) -> Self {
+        self.decode_options = options;
+        self
+    }
+
+    pub fn filter(&self) -> &F {
+        &self.filter
+    }
+
+    /// Accept clients on `listener` forever, proxying each one on its own threads.
+    ///
+    /// After a failed accept (e.g. out of file descriptors) the proxy waits
+    /// before trying again, from 10ms doubling up to a second while the
+    /// failures go on.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        let mut backoff = MIN_ACCEPT_BACKOFF;
+        for client in listener.incoming() {
+            match client {
+                Ok(client) => {
+                    backoff = MIN_ACCEPT_BACKOFF;
+                    let proxy = self.clone();
+                    thread::spawn(move || proxy.handle(client));
+                }
+                Err(error) => {
+                    self.filter.accept_failed(&error);
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Proxy one client until either side closes the connection.
+    ///
+    /// The error that ended the connection is returned and also passed to
+    /// `PacketFilter::disconnected`.
+    pub fn handle(&self, client: TcpStream) -> CodecResult<()> {
+        let connection = ProxyConnection {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            client: client.peer_addr()?,
+            upstream: self.upstream,
+        };
+        let result = self.forward_connection(&connection, client);
+        self.filter.disconnected(&connection, result.as_ref().err());
+        result
+    }
+
+    fn forward_connection(
+        &self,
+        connection: &ProxyConnection,
+        client: TcpStream,
+    ) -> CodecResult<()> {
+        let upstream = TcpStream::connect(self.upstream)?;
+        client.set_nodelay(true)?;
+        upstream.set_nodelay(true)?;
+
+        let to_server = {
+            let proxy = self.clone();
+            let connection = *connection;
+            let (client, upstream) = (client.try_clone()?, upstream.try_clone()?);
+            thread::spawn(move || proxy.forward::<ServerBoundPacket>(&connection, client, upstream))
+        };
+        let to_client = self.forward::<ClientBoundPacket>(connection, upstream, client);
+        let to_server = to_server
+            .join()
+            .unwrap_or_else(|_| Err(CodecError::Other("proxy thread panicked".to_string())));
+        to_client.and(to_server)
+    }
+
+    /// Forward the frames read from `from` to `to` until `from` closes.
+    fn forward<P: Forwarded>(
+        &self,
+        connection: &ProxyConnection,
+        from: TcpStream,
+        mut to: TcpStream,
+    ) -> CodecResult<()> {
+        let mut reader = FrameReader::new(BufReader::new(from), P::DIRECTION)
+            .with_max_frame_length(self.max_frame_length);
+        let mut warnings = Vec::new();
+        // Followed to tell apart the packets sharing an ID
+        let mut state = P::INITIAL_STATE;
+        let result = (|| {
+            while let Some((packet_id, payload)) = reader.read_raw_frame()? {
+                let decoded = P::decode(
+                    state,
+                    packet_id,
+                    payload.clone(),
+                    self.decode_options,
+                    &mut warnings,
+                );
+                state.observe(P::DIRECTION, packet_id);
+                for warning in warnings.drain(..) {
+                    self.filter.warning(connection, warning);
+                }
+                let packet = match decoded {
+                    Ok(packet) => packet,
+                    Err(error) => {
+                        self.filter
+                            .undecodable(connection, P::DIRECTION, packet_id, &error);
+                        to.write_all(&encode_raw_packet(packet_id, false, &payload)?)?;
+                        continue;
+                    }
+                };
+                let original = packet.clone();
+                match packet.filter(&*self.filter, connection) {
+                    // Keep the frame as it came, compressed payloads included
+                    Some(packet) if packet == original => {
+                        to.write_all(&encode_raw_packet(packet_id, false, &payload)?)?;
+                    }
+                    Some(packet) => to.write_all(&packet.encode()?)?,
+                    None => {}
+                }
+            }
+            Ok(())
+        })();
+
+        let from = reader.into_inner().into_inner();
+        match result {
+            // Pass the half close on, the other direction keeps going
+            Ok(()) => {
+                let _ = to.shutdown(Shutdown::Write);
+            }
+            // Unblock the other direction
+            Err(_) => {
+                let _ = from.shutdown(Shutdown::Both);
+                let _ = to.shutdown(Shutdown::Both);
+            }
+        }
+        result
+    }
+}
+
+impl<F> Clone for Proxy<F> {
+    fn clone(&self) -> Self {
+        Self {
+            upstream: self.upstream,
+            filter: Arc::clone(&self.filter),
+            max_frame_length: self.max_frame_length,
+            decode_options: self.decode_options,
+            next_id: Arc::clone(&self.next_id),
+        }
+    }
+}
+
+/// The packet enum of one direction.
+trait Forwarded: Clone + PartialEq + Send + 'static {
+    const DIRECTION: PacketDirection;
+    /// Phase the connection is in when the first packet of this direction arrives.
+    const INITIAL_STATE: ConnectionState;
+
+    /// Decode a packet received while the connection is in `state`.
+    fn decode(
+        state: ConnectionState,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<Self>;
+
+    fn filter<F: PacketFilter>(self, filter: &F, connection: &ProxyConnection) -> Option<Self>;
+
+    fn encode(&self) -> CodecResult<BytesMut>;
+}
+
+impl Forwarded for ServerBoundPacket {
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+    const INITIAL_STATE: ConnectionState = ConnectionState::Handshake;
+
+    fn decode(
+        _: ConnectionState,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<Self> {
+        deserialize_server_bound_with(packet_id, payload, options, warnings)
+    }
+
+    fn filter<F: PacketFilter>(self, filter: &F, connection: &ProxyConnection) -> Option<Self> {
+        filter.server_bound(connection, self)
+    }
+
+    fn encode(&self) -> CodecResult<BytesMut> {
+        ServerBoundPacket::encode(self)
+    }
+}
+
+impl Forwarded for ClientBoundPacket {
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+    // The server only speaks once the client sent Connect
+    const INITIAL_STATE: ConnectionState = ConnectionState::Auth;
+
+    fn decode(
+        state: ConnectionState,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<Self> {
+        deserialize_client_bound_in(state, packet_id, payload, options, warnings)
+    }
+
+    fn filter<F: PacketFilter>(self, filter: &F, connection: &ProxyConnection) -> Option<Self> {
+        filter.client_bound(connection, self)
+    }
+
+    fn encode(&self) -> CodecResult<BytesMut> {
+        ClientBoundPacket::encode(self)
+    }
+}
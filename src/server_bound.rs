@@ -1,12 +1,19 @@
-use crate::codec::{CodecError, CodecResult, Packet, decode_payload};
+use crate::codec::{
+    CodecResult, DecodeOptions, DecodeWarning, Packet, decode_payload_sized, encode_packet,
+    unknown_payload,
+};
+use crate::compression::with_compressor;
 use crate::packets::auth::AuthToken;
 use crate::packets::connection::{Connect, Disconnect, Ping};
 use crate::packets::interface::{BlockChange, ChatMessage};
 use crate::packets::inventory::{DropItemStack, MoveItemStack, SetActiveSlot};
 use crate::packets::player::{ClientMovement, ClientReady, ClientTeleport};
 use crate::packets::setup::{PlayerOptions, RequestAssets, RequestCommonAssetsRebuild};
+use bytes::{Bytes, BytesMut};
 
-#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ServerBoundPacket {
     Connect(Connect),
     Disconnect(Disconnect),
@@ -23,30 +30,146 @@ pub enum ServerBoundPacket {
     SetActiveSlot(SetActiveSlot),
     DropItemStack(DropItemStack),
     MoveItemStack(MoveItemStack),
-    // ...
+    /// A packet this crate doesn't model, kept so it can be logged or forwarded.
+    ///
+    /// `payload` is as it was on the wire, unless `decompressed` is set (see
+    /// `DecodeOptions::decompress_unknown`). `encode_raw_packet(id, decompressed, &payload)`
+    /// frames it again.
+    Unknown {
+        id: u32,
+        payload: Bytes,
+        decompressed: bool,
+    },
+}
+
+impl ServerBoundPacket {
+    /// ID of the packet on the wire.
+    pub fn id(&self) -> u32 {
+        match self {
+            ServerBoundPacket::Connect(_) => Connect::PACKET_ID,
+            ServerBoundPacket::Disconnect(_) => Disconnect::PACKET_ID,
+            ServerBoundPacket::Ping(_) => Ping::PACKET_ID,
+            ServerBoundPacket::AuthToken(_) => AuthToken::PACKET_ID,
+            ServerBoundPacket::RequestAssets(_) => RequestAssets::PACKET_ID,
+            ServerBoundPacket::ClientReady(_) => ClientReady::PACKET_ID,
+            ServerBoundPacket::ClientMovement(_) => ClientMovement::PACKET_ID,
+            ServerBoundPacket::ClientTeleport(_) => ClientTeleport::PACKET_ID,
+            ServerBoundPacket::PlayerOptions(_) => PlayerOptions::PACKET_ID,
+            ServerBoundPacket::RequestCommonAssetsRebuild(_) => RequestCommonAssetsRebuild::PACKET_ID,
+            ServerBoundPacket::ChatMessage(_) => ChatMessage::PACKET_ID,
+            ServerBoundPacket::BlockChange(_) => BlockChange::PACKET_ID,
+            ServerBoundPacket::SetActiveSlot(_) => SetActiveSlot::PACKET_ID,
+            ServerBoundPacket::DropItemStack(_) => DropItemStack::PACKET_ID,
+            ServerBoundPacket::MoveItemStack(_) => MoveItemStack::PACKET_ID,
+            ServerBoundPacket::Unknown { id, .. } => *id,
+        }
+    }
+
+    /// Name of the packet, as in `PacketInfo::name`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ServerBoundPacket::Connect(_) => "Connect",
+            ServerBoundPacket::Disconnect(_) => "Disconnect",
+            ServerBoundPacket::Ping(_) => "Ping",
+            ServerBoundPacket::AuthToken(_) => "AuthToken",
+            ServerBoundPacket::RequestAssets(_) => "RequestAssets",
+            ServerBoundPacket::ClientReady(_) => "ClientReady",
+            ServerBoundPacket::ClientMovement(_) => "ClientMovement",
+            ServerBoundPacket::ClientTeleport(_) => "ClientTeleport",
+            ServerBoundPacket::PlayerOptions(_) => "PlayerOptions",
+            ServerBoundPacket::RequestCommonAssetsRebuild(_) => "RequestCommonAssetsRebuild",
+            ServerBoundPacket::ChatMessage(_) => "ChatMessage",
+            ServerBoundPacket::BlockChange(_) => "BlockChange",
+            ServerBoundPacket::SetActiveSlot(_) => "SetActiveSlot",
+            ServerBoundPacket::DropItemStack(_) => "DropItemStack",
+            ServerBoundPacket::MoveItemStack(_) => "MoveItemStack",
+            ServerBoundPacket::Unknown { .. } => "Unknown",
+        }
+    }
+
+    /// Encode the packet into a complete frame (Length + ID + Payload).
+    pub fn encode(&self) -> CodecResult<BytesMut> {
+        match self {
+            ServerBoundPacket::Connect(packet) => encode_packet(packet),
+            ServerBoundPacket::Disconnect(packet) => encode_packet(packet),
+            ServerBoundPacket::Ping(packet) => encode_packet(packet),
+            ServerBoundPacket::AuthToken(packet) => encode_packet(packet),
+            ServerBoundPacket::RequestAssets(packet) => encode_packet(packet),
+            ServerBoundPacket::ClientReady(packet) => encode_packet(packet),
+            ServerBoundPacket::ClientMovement(packet) => encode_packet(packet),
+            ServerBoundPacket::ClientTeleport(packet) => encode_packet(packet),
+            ServerBoundPacket::PlayerOptions(packet) => encode_packet(packet),
+            ServerBoundPacket::RequestCommonAssetsRebuild(packet) => encode_packet(packet),
+            ServerBoundPacket::ChatMessage(packet) => encode_packet(packet),
+            ServerBoundPacket::BlockChange(packet) => encode_packet(packet),
+            ServerBoundPacket::SetActiveSlot(packet) => encode_packet(packet),
+            ServerBoundPacket::DropItemStack(packet) => encode_packet(packet),
+            ServerBoundPacket::MoveItemStack(packet) => encode_packet(packet),
+            ServerBoundPacket::Unknown {
+                id,
+                payload,
+                decompressed,
+            } => with_compressor(|compressor| compressor.encode_frame(*id, *decompressed, payload)),
+        }
+    }
 }
 
 /// Deserialize a packet based on its ID and payload.
 /// 
 /// The payload should be the raw packet data (excluding length and ID).
 /// This function handles decompression if the packet type requires it.
-pub fn deserialize_server_bound(packet_id: u32, payload: &[u8]) -> CodecResult<ServerBoundPacket> {
+pub fn deserialize_server_bound(packet_id: u32, payload: Bytes) -> CodecResult<ServerBoundPacket> {
+    deserialize_server_bound_with(packet_id, payload, DecodeOptions::default(), &mut Vec::new())
+}
+
+/// `deserialize_server_bound` with explicit options; lenient mode warnings are appended to `warnings`.
+pub fn deserialize_server_bound_with(
+    packet_id: u32,
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<ServerBoundPacket> {
+    deserialize_server_bound_sized(packet_id, payload, options, warnings).map(|(packet, _)| packet)
+}
+
+/// `deserialize_server_bound_with`, also returning the size of the payload once
+/// decompressed (its size on the wire if it isn't compressed).
+pub fn deserialize_server_bound_sized(
+    packet_id: u32,
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<(ServerBoundPacket, usize)> {
     match packet_id {
-        Connect::PACKET_ID => Ok(ServerBoundPacket::Connect(decode_payload(payload)?)),
-        Disconnect::PACKET_ID => Ok(ServerBoundPacket::Disconnect(decode_payload(payload)?)),
-        Ping::PACKET_ID => Ok(ServerBoundPacket::Ping(decode_payload(payload)?)),
-        AuthToken::PACKET_ID => Ok(ServerBoundPacket::AuthToken(decode_payload(payload)?)),
-        RequestAssets::PACKET_ID => Ok(ServerBoundPacket::RequestAssets(decode_payload(payload)?)),
-        ClientReady::PACKET_ID => Ok(ServerBoundPacket::ClientReady(decode_payload(payload)?)),
-        ClientMovement::PACKET_ID => Ok(ServerBoundPacket::ClientMovement(decode_payload(payload)?)),
-        ClientTeleport::PACKET_ID => Ok(ServerBoundPacket::ClientTeleport(decode_payload(payload)?)),
-        PlayerOptions::PACKET_ID => Ok(ServerBoundPacket::PlayerOptions(decode_payload(payload)?)),
-        RequestCommonAssetsRebuild::PACKET_ID => Ok(ServerBoundPacket::RequestCommonAssetsRebuild(decode_payload(payload)?)),
-        ChatMessage::PACKET_ID => Ok(ServerBoundPacket::ChatMessage(decode_payload(payload)?)),
-        BlockChange::PACKET_ID => Ok(ServerBoundPacket::BlockChange(decode_payload(payload)?)),
-        SetActiveSlot::PACKET_ID => Ok(ServerBoundPacket::SetActiveSlot(decode_payload(payload)?)),
-        DropItemStack::PACKET_ID => Ok(ServerBoundPacket::DropItemStack(decode_payload(payload)?)),
-        MoveItemStack::PACKET_ID => Ok(ServerBoundPacket::MoveItemStack(decode_payload(payload)?)),
-        _ => Err(CodecError::Other(format!("Unknown server bound packet id: {}", packet_id))),
+        Connect::PACKET_ID => sized(ServerBoundPacket::Connect, payload, options, warnings),
+        Disconnect::PACKET_ID => sized(ServerBoundPacket::Disconnect, payload, options, warnings),
+        Ping::PACKET_ID => sized(ServerBoundPacket::Ping, payload, options, warnings),
+        AuthToken::PACKET_ID => sized(ServerBoundPacket::AuthToken, payload, options, warnings),
+        RequestAssets::PACKET_ID => sized(ServerBoundPacket::RequestAssets, payload, options, warnings),
+        ClientReady::PACKET_ID => sized(ServerBoundPacket::ClientReady, payload, options, warnings),
+        ClientMovement::PACKET_ID => sized(ServerBoundPacket::ClientMovement, payload, options, warnings),
+        ClientTeleport::PACKET_ID => sized(ServerBoundPacket::ClientTeleport, payload, options, warnings),
+        PlayerOptions::PACKET_ID => sized(ServerBoundPacket::PlayerOptions, payload, options, warnings),
+        RequestCommonAssetsRebuild::PACKET_ID => sized(ServerBoundPacket::RequestCommonAssetsRebuild, payload, options, warnings),
+        ChatMessage::PACKET_ID => sized(ServerBoundPacket::ChatMessage, payload, options, warnings),
+        BlockChange::PACKET_ID => sized(ServerBoundPacket::BlockChange, payload, options, warnings),
+        SetActiveSlot::PACKET_ID => sized(ServerBoundPacket::SetActiveSlot, payload, options, warnings),
+        DropItemStack::PACKET_ID => sized(ServerBoundPacket::DropItemStack, payload, options, warnings),
+        MoveItemStack::PACKET_ID => sized(ServerBoundPacket::MoveItemStack, payload, options, warnings),
+        _ => {
+            let (payload, decompressed) = unknown_payload(payload, options);
+            let size = payload.len();
+            Ok((ServerBoundPacket::Unknown { id: packet_id, payload, decompressed }, size))
+        }
     }
 }
+
+/// Decode the payload of a `P` and wrap it in its variant.
+fn sized<P: Packet>(
+    variant: fn(P) -> ServerBoundPacket,
+    payload: Bytes,
+    options: DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> CodecResult<(ServerBoundPacket, usize)> {
+    decode_payload_sized(payload, options, warnings).map(|(packet, size)| (variant(packet), size))
+}
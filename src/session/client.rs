@@ -0,0 +1,409 @@
+use crate::client_bound::ClientBoundPacket;
+use crate::codec::{
+    CodecError, CodecResult, DEFAULT_MAX_FRAME_LENGTH, DecodeOptions, DecodeWarning, Packet,
+    encode_packet, split_frame,
+};
+use crate::common::Asset;
+use crate::packets::auth::AuthToken;
+use crate::packets::connection::Connect;
+use crate::packets::player::ClientReady;
+use crate::packets::setup::RequestAssets;
+use crate::session::ASSET_PART_SIZE;
+use crate::state::ConnectionState;
+use bytes::{Bytes, BytesMut};
+use std::collections::{HashSet, VecDeque};
+
+/// An asset fully received from the server.
+#[derive(Debug, Clone)]
+pub struct ReceivedAsset {
+    pub asset: Asset,
+    pub data: Vec<u8>,
+}
+
+/// Asset being reassembled from `AssetPart`s.
+#[derive(Debug)]
+struct PendingAsset {
+    asset: Asset,
+    size: usize,
+    data: Vec<u8>,
+}
+
+/// Transport agnostic client side of a connection, for bots and load tests.
+///
+/// Sends `Connect` on creation, answers `AuthGrant`, requests the assets
+/// missing from the cache when `WorldSettings` arrives, reassembles the
+/// `AssetPart`s and sends `ClientReady` once the server sent `JoinWorld`.
+/// Every decoded packet is also handed out by `poll_packet`.
+#[derive(Debug)]
+pub struct ClientSession {
+    state: ConnectionState,
+    incoming: BytesMut,
+    transmit: VecDeque<Bytes>,
+    packets: VecDeque<ClientBoundPacket>,
+    assets: VecDeque<ReceivedAsset>,
+    max_frame_length: usize,
+    decode_options: DecodeOptions,
+    warnings: Vec<DecodeWarning>,
+    access_token: Option<String>,
+    server_authorization_grant: Option<String>,
+    cached_assets: HashSet<String>,
+    pending_asset: Option<PendingAsset>,
+}
+
+impl ClientSession {
+    pub fn new(connect: Connect) -> CodecResult<Self> {
+        let mut session = Self {
+            state: ConnectionState::default(),
+            incoming: BytesMut::new(),
+            transmit: VecDeque::new(),
+            packets: VecDeque::new(),
+            assets: VecDeque::new(),
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            decode_options: DecodeOptions::default(),
+            warnings: Vec::new(),
+            access_token: None,
+            server_authorization_grant: None,
+            cached_assets: HashSet::new(),
+            pending_asset: None,
+        };
+        session.send(&connect)?;
+        Ok(session)
+    }
+
+    /// Reject frames whose payload is longer than `max_frame_length` bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    /// Decode the server's packets strictly, or leniently while recording warnings.
+    pub fn with_decode_options(mut self, options: DecodeOptions) -> Self {
+        self.decode_options = options;
+        self
+    }
+
+    /// Token sent back in `AuthToken` when the server sends `AuthGrant`.
+    pub fn with_access_token(mut self, access_token: impl Into<String>) -> Self {
+        self.access_token = Some(access_token.into());
+        self
+    }
+
+    /// Grant sent back in `AuthToken` along with the access token.
+    pub fn with_server_authorization_grant(mut self, grant: impl Into<String>) -> Self {
+        self.server_authorization_grant = Some(grant.into());
+        self
+    }
+
+    /// Hashes of the assets we already have, they are not requested again.
+    pub fn with_cached_assets(mut self, hashes: impl IntoIterator<Item = String>) -> Self {
+        self.cached_assets.extend(hashes);
+        self
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state == ConnectionState::Closed
+    }
+
+    /// Feed bytes received from the server.
+    pub fn receive(&mut self, data: &[u8]) -> CodecResult<()> {
+        self.incoming.extend_from_slice(data);
+        while !self.is_closed() {
+            let Some((packet_id, payload)) =
+                split_frame(&mut self.incoming, self.max_frame_length)?
+            else {
+                break;
+            };
+            let packet = self.state.decode_client_bound_with(
+                packet_id,
+                payload,
+                self.decode_options,
+                &mut self.warnings,
+            )?;
+            self.handle(&packet)?;
+            self.packets.push_back(packet);
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, packet: &ClientBoundPacket) -> CodecResult<()> {
+        match packet {
+            ClientBoundPacket::AuthGrant(_) => {
+                let token = AuthToken {
+                    access_token: self.access_token.clone(),
+                    server_authorization_grant: self.server_authorization_grant.clone(),
+                };
+                self.send(&token)?;
+            }
+            ClientBoundPacket::WorldSettings(settings) => {
+                let missing = settings
+                    .required_assets
+                    .iter()
+                    .flatten()
+                    .filter(|asset| !self.cached_assets.contains(&asset.hash))
+                    .cloned()
+                    .collect();
+                self.send(&RequestAssets {
+                    assets: Some(missing),
+                })?;
+            }
+            ClientBoundPacket::AssetInitialize(init) => {
+                let size = init.size.max(0) as usize;
+                self.pending_asset = Some(PendingAsset {
+                    asset: init.asset.clone(),
+                    size,
+                    // The announced size is untrusted, reserve at most one part up front
+                    data: Vec::with_capacity(size.min(ASSET_PART_SIZE)),
+                });
+            }
+            ClientBoundPacket::AssetPart(part) => {
+                let pending = self.pending_asset.as_mut().ok_or_else(|| {
+                    CodecError::Other("AssetPart without AssetInitialize".to_string())
+                })?;
+                let part = part.part.as_deref().unwrap_or_default();
+                if pending.data.len() + part.len() > pending.size {
+                    return Err(CodecError::Other(format!(
+                        "Asset {} is larger than the announced {} bytes",
+                        pending.asset.name, pending.size
+                    )));
+                }
+                pending.data.extend_from_slice(part);
+            }
+            ClientBoundPacket::AssetFinalize(_) => {
+                let pending = self.pending_asset.take().ok_or_else(|| {
+                    CodecError::Other("AssetFinalize without AssetInitialize".to_string())
+                })?;
+                if pending.data.len() != pending.size {
+                    return Err(CodecError::Other(format!(
+                        "Asset {} is {} bytes, expected {}",
+                        pending.asset.name,
+                        pending.data.len(),
+                        pending.size
+                    )));
+                }
+                self.cached_assets.insert(pending.asset.hash.clone());
+                self.assets.push_back(ReceivedAsset {
+                    asset: pending.asset,
+                    data: pending.data,
+                });
+            }
+            ClientBoundPacket::JoinWorld(_) => self.send(&ClientReady)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Queue a packet for the server.
+    pub fn send<P: Packet>(&mut self, packet: &P) -> CodecResult<()> {
+        self.state.send(packet)?;
+        self.transmit.push_back(encode_packet(packet)?.freeze());
+        Ok(())
+    }
+
+    /// Next frame to write to the socket.
+    pub fn poll_transmit(&mut self) -> Option<Bytes> {
+        self.transmit.pop_front()
+    }
+
+    /// Next packet received from the server.
+    pub fn poll_packet(&mut self) -> Option<ClientBoundPacket> {
+        self.packets.pop_front()
+    }
+
+    /// Next asset fully received from the server.
+    pub fn poll_asset(&mut self) -> Option<ReceivedAsset> {
+        self.assets.pop_front()
+    }
+
+    /// Warnings recorded while decoding leniently since the last call.
+    pub fn take_warnings(&mut self) -> Vec<DecodeWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::auth::AuthGrant;
+    use crate::packets::connection::{ClientType, PROTOCOL_HASH_LENGTH};
+    use crate::packets::player::JoinWorld;
+    use crate::packets::setup::{AssetFinalize, AssetInitialize, AssetPart, WorldSettings};
+    use crate::server_bound::ServerBoundPacket;
+    use crate::session::{ServerEvent, ServerSession};
+    use uuid::Uuid;
+
+    fn client() -> ClientSession {
+        ClientSession::new(Connect {
+            protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Game,
+            language: None,
+            identity_token: None,
+            uuid: Uuid::from_u128(1),
+            username: "Player".to_string(),
+            referral_data: None,
+            referral_source: None,
+        })
+        .unwrap()
+    }
+
+    fn asset(name: &str) -> Asset {
+        Asset {
+            hash: format!("{name:0>64}"),
+            name: name.to_string(),
+        }
+    }
+
+    fn world_settings(assets: Vec<Asset>) -> WorldSettings {
+        WorldSettings {
+            game_version: "2026.01.13".to_string(),
+            world_seed: 1,
+            time_of_day: 0.5,
+            weather_seed: 2,
+            world_height: 320,
+            required_assets: Some(assets),
+        }
+    }
+
+    /// Hand the frames queued by the client to the server and back.
+    fn exchange(client: &mut ClientSession, server: &mut ServerSession) -> Vec<ServerEvent> {
+        while let Some(frame) = client.poll_transmit() {
+            server.receive(&frame).unwrap();
+        }
+        while let Some(frame) = server.poll_transmit() {
+            client.receive(&frame).unwrap();
+        }
+        std::iter::from_fn(|| server.poll_event()).collect()
+    }
+
+    /// A client that got `WorldSettings` and is waiting for assets.
+    fn in_setup() -> ClientSession {
+        let mut client = client();
+        client
+            .receive(&encode_packet(&world_settings(Vec::new())).unwrap())
+            .unwrap();
+        assert_eq!(client.state(), ConnectionState::Setup);
+        client
+    }
+
+    #[test]
+    fn login_against_server_session() {
+        let (sky, grass) = (asset("Sky.png"), asset("Grass.png"));
+        let mut client = client()
+            .with_access_token("access")
+            .with_cached_assets([grass.hash.clone()]);
+        let mut server = ServerSession::new();
+
+        let events = exchange(&mut client, &mut server);
+        assert!(matches!(&events[..], [ServerEvent::PlayerConnected(_)]));
+
+        // AuthToken answers AuthGrant
+        server
+            .send(&AuthGrant {
+                authorization_grant: Some("grant".to_string()),
+                server_identity_token: None,
+            })
+            .unwrap();
+        assert!(exchange(&mut client, &mut server).is_empty());
+        let events = exchange(&mut client, &mut server);
+        assert!(matches!(
+            &events[..],
+            [ServerEvent::Packet(ServerBoundPacket::AuthToken(token))]
+                if token.access_token.as_deref() == Some("access")
+        ));
+
+        // Only the assets missing from the cache are requested
+        server
+            .send(&world_settings(vec![sky.clone(), grass.clone()]))
+            .unwrap();
+        exchange(&mut client, &mut server);
+        let events = exchange(&mut client, &mut server);
+        assert!(matches!(
+            &events[..],
+            [ServerEvent::AssetsRequested(assets)] if assets[..] == [sky.clone()]
+        ));
+
+        let data: Bytes = (0..ASSET_PART_SIZE + 10).map(|i| i as u8).collect();
+        server.send_asset(sky.clone(), data.clone()).unwrap();
+        exchange(&mut client, &mut server);
+        let received = client.poll_asset().unwrap();
+        assert_eq!(received.asset, sky);
+        assert_eq!(received.data, data);
+        assert!(client.poll_asset().is_none());
+
+        // ClientReady follows JoinWorld
+        server
+            .send(&JoinWorld {
+                clear_world: true,
+                fade_in_out: false,
+                world_uuid: Uuid::from_u128(2),
+            })
+            .unwrap();
+        exchange(&mut client, &mut server);
+        assert_eq!(client.state(), ConnectionState::Play);
+        let events = exchange(&mut client, &mut server);
+        assert!(matches!(&events[..], [ServerEvent::Ready]));
+        assert_eq!(server.state(), ConnectionState::Play);
+
+        let names: Vec<_> = std::iter::from_fn(|| client.poll_packet())
+            .map(|packet| packet.name())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "AuthGrant",
+                "WorldSettings",
+                "AssetInitialize",
+                "AssetPart",
+                "AssetPart",
+                "AssetFinalize",
+                "JoinWorld"
+            ]
+        );
+    }
+
+    #[test]
+    fn asset_part_overrunning_the_size() {
+        let mut client = in_setup();
+        let initialize = AssetInitialize {
+            asset: asset("Sky.png"),
+            size: 2,
+        };
+        client
+            .receive(&encode_packet(&initialize).unwrap())
+            .unwrap();
+        let part = AssetPart {
+            part: Some(Bytes::from_static(b"sky")),
+        };
+        assert!(matches!(
+            client.receive(&encode_packet(&part).unwrap()),
+            Err(CodecError::Other(_))
+        ));
+        assert!(client.poll_asset().is_none());
+    }
+
+    #[test]
+    fn asset_shorter_than_its_size() {
+        let mut client = in_setup();
+        let initialize = AssetInitialize {
+            asset: asset("Sky.png"),
+            size: 5,
+        };
+        let part = AssetPart {
+            part: Some(Bytes::from_static(b"sky")),
+        };
+        let frames = [
+            encode_packet(&initialize).unwrap(),
+            encode_packet(&part).unwrap(),
+        ]
+        .concat();
+        client.receive(&frames).unwrap();
+        assert!(matches!(
+            client.receive(&encode_packet(&AssetFinalize).unwrap()),
+            Err(CodecError::Other(_))
+        ));
+        assert!(client.poll_asset().is_none());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod client;
+pub mod server;
+
+pub use client::*;
+pub use server::*;
@@ -0,0 +1,468 @@
+use crate::codec::{
+    CodecError, CodecResult, DEFAULT_MAX_FRAME_LENGTH, DecodeOptions, DecodeWarning, Packet,
+    split_frame,
+};
+use crate::common::Asset;
+use crate::packets::connection::{Connect, Disconnect, DisconnectType, Pong, PongType};
+use crate::packets::interface::ChatMessage;
+use crate::packets::player::ClientMovement;
+use crate::packets::setup::{AssetFinalize, AssetInitialize, AssetPart};
+use crate::server_bound::ServerBoundPacket;
+use crate::state::ConnectionState;
+use crate::version::{PacketLayout, ProtocolVersion, ProtocolVersions};
+use bytes::{Bytes, BytesMut};
+use std::collections::VecDeque;
+
+/// Largest chunk of asset data sent in a single `AssetPart`.
+pub const ASSET_PART_SIZE: usize = 4_096_000;
+
+/// What happened on a `ServerSession` after feeding it bytes.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum ServerEvent {
+    /// The client sent a `Connect` that passed the protocol version check.
+    PlayerConnected(Connect),
+    /// The client asked for the assets it is missing.
+    AssetsRequested(Vec<Asset>),
+    /// The client finished loading and is in game.
+    Ready,
+    Moved(ClientMovement),
+    Chat(ChatMessage),
+    /// Either side sent a `Disconnect`; the session is closed.
+    Disconnected(Disconnect),
+    /// Any other packet, for the application to handle.
+    Packet(ServerBoundPacket),
+}
+
+/// Transport agnostic server side of a connection.
+///
+/// Feed it the bytes read from the socket with `receive`, then drain
+/// `poll_event` and `poll_transmit`. It never does IO itself, so it can be
+/// driven by tokio, mio or a test harness alike.
+///
+/// The session checks that each packet is allowed in the current phase,
+/// negotiates the protocol version and answers `Ping`s. The rest of the login
+/// is up to the application, in reaction to the events: sending `AuthGrant`
+/// (or skipping auth), `WorldSettings`, the requested assets with `send_asset`
+/// and finally `JoinWorld`.
+#[derive(Debug)]
+pub struct ServerSession {
+    state: ConnectionState,
+    incoming: BytesMut,
+    transmit: VecDeque<Bytes>,
+    events: VecDeque<ServerEvent>,
+    max_frame_length: usize,
+    decode_options: DecodeOptions,
+    warnings: Vec<DecodeWarning>,
+    versions: Option<ProtocolVersions>,
+    /// Version negotiated with the client, once it connected.
+    version: Option<ProtocolVersion>,
+}
+
+impl ServerSession {
+    pub fn new() -> Self {
+        Self {
+            state: ConnectionState::default(),
+            incoming: BytesMut::new(),
+            transmit: VecDeque::new(),
+            events: VecDeque::new(),
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            decode_options: DecodeOptions::default(),
+            warnings: Vec::new(),
+            versions: None,
+            version: None,
+        }
+    }
+
+    /// Reject frames whose payload is longer than `max_frame_length` bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    /// Decode the client's packets strictly, or leniently while recording warnings.
+    pub fn with_decode_options(mut self, options: DecodeOptions) -> Self {
+        self.decode_options = options;
+        self
+    }
+
+    /// Check `Connect.protocol_hash` against `versions`, disconnecting clients
+    /// that don't match and speaking the `PacketLayout` of the others. Without
+    /// it every client is accepted and spoken to with `PacketLayout::CURRENT`.
+    pub fn with_versions(mut self, versions: ProtocolVersions) -> Self {
+        self.versions = Some(versions);
+        self
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state == ConnectionState::Closed
+    }
+
+    /// Version negotiated with the client, when checked with `with_versions`.
+    pub fn version(&self) -> Option<&ProtocolVersion> {
+        self.version.as_ref()
+    }
+
+    /// Layout the client's packets are read and written with.
+    pub fn layout(&self) -> PacketLayout {
+        self.version
+            .as_ref()
+            .map_or(PacketLayout::CURRENT, |version| version.layout)
+    }
+
+    /// Feed bytes received from the client.
+    ///
+    /// Fails on malformed frames and on packets that are not allowed in the
+    /// current phase (`CodecError::ProtocolViolation`).
+    pub fn receive(&mut self, data: &[u8]) -> CodecResult<()> {
+        self.incoming.extend_from_slice(data);
+        while !self.is_closed() {
+            let Some((packet_id, payload)) =
+                split_frame(&mut self.incoming, self.max_frame_length)?
+            else {
+                break;
+            };
+            let packet = self.layout().decode_server_bound(
+                &mut self.state,
+                packet_id,
+                payload,
+                self.decode_options,
+                &mut self.warnings,
+            )?;
+            self.handle(packet)?;
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, packet: ServerBoundPacket) -> CodecResult<()> {
+        let event = match packet {
+            ServerBoundPacket::Connect(connect) => {
+                let negotiated = self
+                    .versions
+                    .as_ref()
+                    .map(|versions| versions.negotiate(&connect).cloned());
+                match negotiated {
+                    Some(Err(disconnect)) => {
+                        self.send(&disconnect)?;
+                        ServerEvent::Disconnected(disconnect)
+                    }
+                    Some(Ok(version)) => {
+                        self.version = Some(version);
+                        ServerEvent::PlayerConnected(connect)
+                    }
+                    None => ServerEvent::PlayerConnected(connect),
+                }
+            }
+            ServerBoundPacket::Ping(ping) => {
+                return self.send(&Pong {
+                    pong_type: PongType::Pong,
+                    time: ping.time,
+                });
+            }
+            ServerBoundPacket::RequestAssets(request) => {
+                ServerEvent::AssetsRequested(request.assets.unwrap_or_default())
+            }
+            ServerBoundPacket::ClientReady(_) => ServerEvent::Ready,
+            ServerBoundPacket::ClientMovement(movement) => ServerEvent::Moved(movement),
+            ServerBoundPacket::ChatMessage(message) => ServerEvent::Chat(message),
+            ServerBoundPacket::Disconnect(disconnect) => ServerEvent::Disconnected(disconnect),
+            packet => ServerEvent::Packet(packet),
+        };
+        self.events.push_back(event);
+        Ok(())
+    }
+
+    /// Queue a packet for the client.
+    pub fn send<P: Packet>(&mut self, packet: &P) -> CodecResult<()> {
+        self.state.send(packet)?;
+        self.transmit
+            .push_back(self.layout().encode(packet)?.freeze());
+        Ok(())
+    }
+
+    /// Queue an asset: `AssetInitialize`, its data split in `AssetPart`s, then `AssetFinalize`.
+    ///
+    /// The parts are slices of `data`, it is not copied before compression.
+    /// Fails without sending anything if `data` is larger than `AssetInitialize`
+    /// can announce.
+    pub fn send_asset(&mut self, asset: Asset, data: Bytes) -> CodecResult<()> {
+        let size = announced_size(&asset, data.len())?;
+        self.send(&AssetInitialize { asset, size })?;
+        for start in (0..data.len()).step_by(ASSET_PART_SIZE) {
+            let end = (start + ASSET_PART_SIZE).min(data.len());
+            self.send(&AssetPart {
+                part: Some(data.slice(start..end)),
+            })?;
+        }
+        self.send(&AssetFinalize)
+    }
+
+    /// Kick the client with a reason.
+    pub fn disconnect(&mut self, reason: impl Into<String>) -> CodecResult<()> {
+        self.send(&Disconnect {
+            reason: Some(reason.into()),
+            disconnect_type: DisconnectType::Kick,
+        })
+    }
+
+    /// Next frame to write to the socket.
+    pub fn poll_transmit(&mut self) -> Option<Bytes> {
+        self.transmit.pop_front()
+    }
+
+    pub fn poll_event(&mut self) -> Option<ServerEvent> {
+        self.events.pop_front()
+    }
+
+    /// Warnings recorded while decoding leniently since the last call.
+    pub fn take_warnings(&mut self) -> Vec<DecodeWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+/// Size of an asset as `AssetInitialize` announces it.
+fn announced_size(asset: &Asset, length: usize) -> CodecResult<i32> {
+    i32::try_from(length).map_err(|_| {
+        CodecError::Other(format!(
+            "Asset {} is {length} bytes, more than AssetInitialize can announce",
+            asset.name
+        ))
+    })
+}
+
+impl Default for ServerSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_bound::ClientBoundPacket;
+    use crate::codec::encode_packet;
+    use crate::packets::auth::{AuthGrant, AuthToken};
+    use crate::packets::connection::{ClientType, PROTOCOL_HASH_LENGTH, Ping};
+    use crate::packets::player::{ClientReady, JoinWorld};
+    use crate::packets::setup::{RequestAssets, WorldSettings};
+    use crate::version::{PacketLayout, ProtocolVersion, ProtocolVersions};
+    use uuid::Uuid;
+
+    fn connect(hash: &str) -> Connect {
+        Connect {
+            protocol_hash: hash.repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Game,
+            language: None,
+            identity_token: None,
+            uuid: Uuid::from_u128(1),
+            username: "Player".to_string(),
+            referral_data: None,
+            referral_source: None,
+        }
+    }
+
+    fn session() -> ServerSession {
+        let current = ProtocolVersion::new("a".repeat(64), "2026.01.13", PacketLayout::V1);
+        ServerSession::new().with_versions(ProtocolVersions::new(current))
+    }
+
+    fn receive<P: Packet>(session: &mut ServerSession, packet: &P) {
+        session.receive(&encode_packet(packet).unwrap()).unwrap();
+    }
+
+    fn events(session: &mut ServerSession) -> Vec<ServerEvent> {
+        std::iter::from_fn(|| session.poll_event()).collect()
+    }
+
+    /// Decode the frames queued by `session`, as the client would in `state`.
+    fn sent(session: &mut ServerSession, state: &mut ConnectionState) -> Vec<ClientBoundPacket> {
+        let mut frames = BytesMut::new();
+        while let Some(frame) = session.poll_transmit() {
+            frames.extend_from_slice(&frame);
+        }
+        let mut packets = Vec::new();
+        while let Some((id, payload)) = split_frame(&mut frames, DEFAULT_MAX_FRAME_LENGTH).unwrap()
+        {
+            packets.push(state.decode_client_bound(id, payload).unwrap());
+        }
+        packets
+    }
+
+    fn asset() -> Asset {
+        Asset {
+            hash: "1".repeat(64),
+            name: "Sky.png".to_string(),
+        }
+    }
+
+    #[test]
+    fn handshake_and_setup() {
+        let mut session = session();
+        // What the client saw after sending Connect
+        let mut client = ConnectionState::Auth;
+
+        receive(&mut session, &connect("a"));
+        let [ServerEvent::PlayerConnected(connect)] = &events(&mut session)[..] else {
+            panic!("expected PlayerConnected");
+        };
+        assert_eq!(connect.username, "Player");
+        assert_eq!(session.version().unwrap().game_version, "2026.01.13");
+        assert_eq!(session.state(), ConnectionState::Auth);
+        assert!(session.poll_transmit().is_none());
+
+        session
+            .send(&AuthGrant {
+                authorization_grant: Some("grant".to_string()),
+                server_identity_token: None,
+            })
+            .unwrap();
+        receive(
+            &mut session,
+            &AuthToken {
+                access_token: Some("access".to_string()),
+                server_authorization_grant: None,
+            },
+        );
+        assert!(matches!(
+            &events(&mut session)[..],
+            [ServerEvent::Packet(ServerBoundPacket::AuthToken(_))]
+        ));
+        session
+            .send(&WorldSettings {
+                game_version: "2026.01.13".to_string(),
+                world_seed: 1,
+                time_of_day: 0.5,
+                weather_seed: 2,
+                world_height: 320,
+                required_assets: Some(vec![asset()]),
+            })
+            .unwrap();
+        assert_eq!(session.state(), ConnectionState::Setup);
+
+        // Pings are answered without the application
+        receive(&mut session, &Ping { time: 42 });
+        assert!(events(&mut session).is_empty());
+        receive(
+            &mut session,
+            &RequestAssets {
+                assets: Some(vec![asset()]),
+            },
+        );
+        assert!(matches!(
+            &events(&mut session)[..],
+            [ServerEvent::AssetsRequested(assets)] if assets[..] == [asset()]
+        ));
+        session
+            .send_asset(asset(), Bytes::from_static(b"sky"))
+            .unwrap();
+        session
+            .send(&JoinWorld {
+                clear_world: true,
+                fade_in_out: false,
+                world_uuid: Uuid::from_u128(2),
+            })
+            .unwrap();
+
+        let names: Vec<_> = sent(&mut session, &mut client)
+            .into_iter()
+            .map(|packet| match packet {
+                ClientBoundPacket::Pong(pong) => {
+                    assert_eq!(pong.time, 42);
+                    "Pong"
+                }
+                ClientBoundPacket::AssetPart(part) => {
+                    assert_eq!(part.part.as_deref(), Some(&b"sky"[..]));
+                    "AssetPart"
+                }
+                packet => packet.name(),
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "AuthGrant",
+                "WorldSettings",
+                "Pong",
+                "AssetInitialize",
+                "AssetPart",
+                "AssetFinalize",
+                "JoinWorld"
+            ]
+        );
+        assert_eq!(client, ConnectionState::Play);
+
+        receive(&mut session, &ClientReady);
+        assert!(matches!(&events(&mut session)[..], [ServerEvent::Ready]));
+        receive(
+            &mut session,
+            &Disconnect {
+                reason: None,
+                disconnect_type: DisconnectType::Leave,
+            },
+        );
+        assert!(matches!(
+            &events(&mut session)[..],
+            [ServerEvent::Disconnected(_)]
+        ));
+        assert!(session.is_closed());
+        assert!(session.poll_transmit().is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_protocol_hash() {
+        let mut session = session();
+        receive(&mut session, &connect("b"));
+        let [ServerEvent::Disconnected(disconnect)] = &events(&mut session)[..] else {
+            panic!("expected Disconnected");
+        };
+        assert_eq!(disconnect.disconnect_type, DisconnectType::Kick);
+        assert!(
+            disconnect
+                .reason
+                .as_deref()
+                .unwrap()
+                .starts_with("Unknown protocol version")
+        );
+        assert!(session.is_closed());
+        assert!(session.version().is_none());
+
+        let mut client = ConnectionState::Auth;
+        assert!(matches!(
+            &sent(&mut session, &mut client)[..],
+            [ClientBoundPacket::Disconnect(sent)] if sent == disconnect
+        ));
+        // Nothing more is read from a closed session
+        receive(&mut session, &Ping { time: 1 });
+        assert!(events(&mut session).is_empty());
+    }
+
+    #[test]
+    fn rejects_packets_out_of_phase() {
+        let mut session = session();
+        let frame = encode_packet(&ClientReady).unwrap();
+        assert!(matches!(
+            session.receive(&frame),
+            Err(CodecError::ProtocolViolation {
+                state: ConnectionState::Handshake,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn asset_size_must_fit_in_an_i32() {
+        assert_eq!(announced_size(&asset(), 3).unwrap(), 3);
+        assert_eq!(
+            announced_size(&asset(), i32::MAX as usize).unwrap(),
+            i32::MAX
+        );
+        assert!(matches!(
+            announced_size(&asset(), i32::MAX as usize + 1),
+            Err(CodecError::Other(_))
+        ));
+    }
+}
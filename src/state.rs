@@ -0,0 +1,482 @@
+//! Phases of a connection and the packets allowed in each of them.
+//!
+//! A connection goes through `Handshake` (Connect), `Auth` (AuthGrant /
+//! AuthToken / ServerAuthToken, ended by WorldSettings), `Setup` (assets, world
+//! loading) and finally `Play` once the server sent `JoinWorld`. Some IDs mean
+//! different packets depending on the phase (24 is AssetInitialize during setup
+//! and ClientReady in play, 21 is WorldSettings before setup and
+//! WorldLoadProgress during it), so decoding goes through the current state.
+//! IDs no phase knows about are passed through as `Unknown`.
+
+use crate::client_bound::{ClientBoundPacket, deserialize_client_bound_sized};
+use crate::codec::{CodecError, CodecResult, DecodeOptions, DecodeWarning, Packet};
+use crate::packet::{PacketDirection, PacketInfo, PacketRegistry, ids_are_unique, packet_info};
+use crate::packets::auth::{AuthGrant, AuthToken, ServerAuthToken};
+use crate::packets::connection::{Connect, Disconnect, Ping, Pong};
+use crate::packets::entities::{ApplyKnockback, ChangeVelocity, EntityUpdates};
+use crate::packets::interface::{BlockChange, ChatMessage, ServerInfo};
+use crate::packets::inventory::{DropItemStack, MoveItemStack, SetActiveSlot};
+use crate::packets::player::{ClientMovement, ClientReady, ClientTeleport, JoinWorld};
+use crate::packets::setup::{
+    AssetFinalize, AssetInitialize, AssetPart, PlayerOptions, RequestAssets,
+    RequestCommonAssetsRebuild, SetClientId, SetEntitySeed, SetTimeDilation, SetUpdateRate,
+    UpdateFeatures, ViewRadius, WorldLoadFinished, WorldLoadProgress, WorldSettings,
+};
+use crate::packets::world::{PlaySoundEvent2D, PlaySoundEvent3D, ServerSetBlock, SetChunk};
+use crate::packets::worldmap::{UpdateWorldMap, UpdateWorldMapSettings};
+use crate::server_bound::{ServerBoundPacket, deserialize_server_bound_sized};
+use bytes::Bytes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ConnectionState {
+    /// Waiting for the client's `Connect`.
+    #[default]
+    Handshake,
+    /// Exchanging auth tokens, until the server sends `WorldSettings`.
+    /// Servers without authentication send it straight away.
+    Auth,
+    /// Sending world settings and assets, loading the world.
+    Setup,
+    /// In game.
+    Play,
+    /// A `Disconnect` went through, nothing else is accepted.
+    Closed,
+}
+
+const HANDSHAKE_PACKETS: &[PacketInfo] = &[packet_info!(Disconnect), packet_info!(Connect)];
+
+const AUTH_PACKETS: &[PacketInfo] = &[
+    packet_info!(Disconnect),
+    packet_info!(AuthToken),
+    packet_info!(AuthGrant),
+    packet_info!(ServerAuthToken),
+    packet_info!(WorldSettings),
+];
+
+const SETUP_PACKETS: &[PacketInfo] = &[
+    // Both directions
+    packet_info!(Disconnect),
+    // Server bound
+    packet_info!(Ping),
+    packet_info!(RequestAssets),
+    packet_info!(PlayerOptions),
+    packet_info!(RequestCommonAssetsRebuild),
+    // Client bound
+    packet_info!(Pong),
+    packet_info!(AssetInitialize),
+    packet_info!(AssetPart),
+    packet_info!(AssetFinalize),
+    packet_info!(SetUpdateRate),
+    packet_info!(SetTimeDilation),
+    packet_info!(UpdateFeatures),
+    packet_info!(ViewRadius),
+    packet_info!(SetClientId),
+    packet_info!(SetEntitySeed),
+    packet_info!(WorldLoadProgress),
+    packet_info!(WorldLoadFinished),
+    packet_info!(JoinWorld),
+];
+
+const PLAY_PACKETS: &[PacketInfo] = &[
+    // Both directions
+    packet_info!(Disconnect),
+    packet_info!(ChatMessage),
+    // Server bound
+    packet_info!(Ping),
+    packet_info!(ClientReady),
+    packet_info!(ClientMovement),
+    packet_info!(ClientTeleport),
+    packet_info!(PlayerOptions),
+    packet_info!(RequestCommonAssetsRebuild),
+    packet_info!(BlockChange),
+    packet_info!(SetActiveSlot),
+    packet_info!(DropItemStack),
+    packet_info!(MoveItemStack),
+    // Client bound
+    packet_info!(Pong),
+    packet_info!(SetUpdateRate),
+    packet_info!(SetTimeDilation),
+    packet_info!(UpdateFeatures),
+    packet_info!(ViewRadius),
+    packet_info!(ApplyKnockback),
+    packet_info!(ChangeVelocity),
+    packet_info!(SetClientId),
+    packet_info!(JoinWorld),
+    packet_info!(SetChunk),
+    packet_info!(ServerSetBlock),
+    packet_info!(PlaySoundEvent2D),
+    packet_info!(PlaySoundEvent3D),
+    packet_info!(SetEntitySeed),
+    packet_info!(EntityUpdates),
+    packet_info!(ServerInfo),
+    packet_info!(UpdateWorldMapSettings),
+    packet_info!(UpdateWorldMap),
+];
+
+// Within a phase an ID must name a single packet per direction.
+const _: () = assert!(
+    ids_are_unique(HANDSHAKE_PACKETS)
+        && ids_are_unique(AUTH_PACKETS)
+        && ids_are_unique(SETUP_PACKETS)
+        && ids_are_unique(PLAY_PACKETS),
+    "two packets in the same phase and direction share a packet id"
+);
+
+/// Whether `info` describes the packet `P`.
+fn is<P: Packet>(info: &PacketInfo) -> bool {
+    info.id == P::PACKET_ID && info.direction == P::DIRECTION
+}
+
+impl ConnectionState {
+    /// Packets that may travel in either direction while in this state.
+    pub const fn packets(self) -> &'static [PacketInfo] {
+        match self {
+            ConnectionState::Handshake => HANDSHAKE_PACKETS,
+            ConnectionState::Auth => AUTH_PACKETS,
+            ConnectionState::Setup => SETUP_PACKETS,
+            ConnectionState::Play => PLAY_PACKETS,
+            ConnectionState::Closed => &[],
+        }
+    }
+
+    /// Look up the packet an ID refers to in this state.
+    pub fn get(self, direction: PacketDirection, id: u32) -> CodecResult<&'static PacketInfo> {
+        self.packets()
+            .iter()
+            .find(|info| info.direction.overlaps(direction) && info.id == id)
+            .ok_or(CodecError::ProtocolViolation {
+                state: self,
+                direction,
+                id,
+            })
+    }
+
+    /// Whether `id` is a packet this crate doesn't know about at all. Those are
+    /// decoded as `Unknown` in any open state and don't change it.
+    fn is_unknown(self, direction: PacketDirection, id: u32) -> bool {
+        self != ConnectionState::Closed && PacketRegistry::new().get(direction, id).is_none()
+    }
+
+    /// State the connection is in once `info` went through.
+    pub fn next(self, info: &PacketInfo) -> ConnectionState {
+        match self {
+            _ if is::<Disconnect>(info) => ConnectionState::Closed,
+            ConnectionState::Handshake if is::<Connect>(info) => ConnectionState::Auth,
+            // WorldSettings shares its ID with WorldLoadProgress, so it is
+            // only valid here and is what starts the setup phase
+            ConnectionState::Auth if is::<WorldSettings>(info) => ConnectionState::Setup,
+            ConnectionState::Setup if is::<JoinWorld>(info) => ConnectionState::Play,
+            state => state,
+        }
+    }
+
+    /// Check that a packet is allowed in this state and advance past it.
+    pub fn advance(
+        &mut self,
+        direction: PacketDirection,
+        id: u32,
+    ) -> CodecResult<&'static PacketInfo> {
+        let info = self.get(direction, id)?;
+        *self = self.next(info);
+        Ok(info)
+    }
+
+    /// Typed version of `advance`, to call before sending `P`.
+    pub fn send<P: Packet>(&mut self, _packet: &P) -> CodecResult<()> {
+        self.advance(P::DIRECTION, P::PACKET_ID).map(|_| ())
+    }
+
+    /// Follow the phase from packets seen in a single direction, e.g. by a
+    /// proxy forwarding each direction on its own. Packets out of phase are
+    /// ignored instead of rejected.
+    ///
+    /// The server only speaks once the client connected, so a client bound
+    /// stream is followed from `Auth`.
+    pub fn observe(&mut self, direction: PacketDirection, id: u32) {
+        if let Ok(info) = self.get(direction, id) {
+            *self = self.next(info);
+        }
+    }
+
+    /// Decode a server bound packet, rejecting it if it is out of phase.
+    pub fn decode_server_bound(
+        &mut self,
+        packet_id: u32,
+        payload: Bytes,
+    ) -> CodecResult<ServerBoundPacket> {
+        self.decode_server_bound_with(
+            packet_id,
+            payload,
+            DecodeOptions::default(),
+            &mut Vec::new(),
+        )
+    }
+
+    /// `decode_server_bound` with explicit options; lenient mode warnings are appended to `warnings`.
+    pub fn decode_server_bound_with(
+        &mut self,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<ServerBoundPacket> {
+        self.decode_server_bound_sized(packet_id, payload, options, warnings)
+            .map(|(packet, _)| packet)
+    }
+
+    /// `decode_server_bound_with`, also returning the size of the payload once decompressed.
+    pub fn decode_server_bound_sized(
+        &mut self,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<(ServerBoundPacket, usize)> {
+        if self.is_unknown(PacketDirection::Serverbound, packet_id) {
+            return deserialize_server_bound_sized(packet_id, payload, options, warnings);
+        }
+        self.get(PacketDirection::Serverbound, packet_id)?;
+        let decoded = deserialize_server_bound_sized(packet_id, payload, options, warnings)?;
+        self.advance(PacketDirection::Serverbound, packet_id)?;
+        Ok(decoded)
+    }
+
+    /// Decode a client bound packet, rejecting it if it is out of phase.
+    pub fn decode_client_bound(
+        &mut self,
+        packet_id: u32,
+        payload: Bytes,
+    ) -> CodecResult<ClientBoundPacket> {
+        self.decode_client_bound_with(
+            packet_id,
+            payload,
+            DecodeOptions::default(),
+            &mut Vec::new(),
+        )
+    }
+
+    /// `decode_client_bound` with explicit options; lenient mode warnings are appended to `warnings`.
+    pub fn decode_client_bound_with(
+        &mut self,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<ClientBoundPacket> {
+        self.decode_client_bound_sized(packet_id, payload, options, warnings)
+            .map(|(packet, _)| packet)
+    }
+
+    /// `decode_client_bound_with`, also returning the size of the payload once decompressed.
+    pub fn decode_client_bound_sized(
+        &mut self,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<(ClientBoundPacket, usize)> {
+        if self.is_unknown(PacketDirection::Clientbound, packet_id) {
+            return deserialize_client_bound_sized(*self, packet_id, payload, options, warnings);
+        }
+        self.get(PacketDirection::Clientbound, packet_id)?;
+        let decoded = deserialize_client_bound_sized(*self, packet_id, payload, options, warnings)?;
+        self.advance(PacketDirection::Clientbound, packet_id)?;
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_bound::deserialize_client_bound_in;
+    use crate::codec::{encode_packet, split_frame};
+    use crate::common::Asset;
+    use crate::packets::connection::{ClientType, PROTOCOL_HASH_LENGTH};
+    use crate::packets::player::ClientMovement;
+    use uuid::Uuid;
+
+    /// Frame `packet` and decode it back through `state`, as a proxy in the
+    /// middle of the connection would. Returns the decoded packet's name.
+    fn relay<P: Packet>(state: &mut ConnectionState, packet: &P) -> CodecResult<&'static str> {
+        let mut frame = encode_packet(packet)?;
+        let (id, payload) = split_frame(&mut frame, P::MAX_SIZE + 8)?.unwrap();
+        let mut warnings = Vec::new();
+        if P::DIRECTION == PacketDirection::Serverbound {
+            state
+                .decode_server_bound_with(id, payload, DecodeOptions::STRICT, &mut warnings)
+                .map(|packet| packet.name())
+        } else {
+            state
+                .decode_client_bound_with(id, payload, DecodeOptions::STRICT, &mut warnings)
+                .map(|packet| packet.name())
+        }
+    }
+
+    fn asset(name: &str) -> Asset {
+        Asset {
+            hash: format!("{name:0>64}"),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn login_sequence() {
+        let mut state = ConnectionState::default();
+        let connect = Connect {
+            protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+            client_type: ClientType::Game,
+            language: Some("en-US".to_string()),
+            identity_token: Some("identity".to_string()),
+            uuid: Uuid::from_u128(1),
+            username: "Player".to_string(),
+            referral_data: None,
+            referral_source: None,
+        };
+        assert_eq!(relay(&mut state, &connect).unwrap(), "Connect");
+        assert_eq!(state, ConnectionState::Auth);
+
+        let grant = AuthGrant {
+            authorization_grant: Some("grant".to_string()),
+            server_identity_token: Some("identity".to_string()),
+        };
+        relay(&mut state, &grant).unwrap();
+        let token = AuthToken {
+            access_token: Some("access".to_string()),
+            server_authorization_grant: Some("grant".to_string()),
+        };
+        relay(&mut state, &token).unwrap();
+        let server_token = ServerAuthToken {
+            server_access_token: Some("access".to_string()),
+            password_challenge: None,
+        };
+        relay(&mut state, &server_token).unwrap();
+        let settings = WorldSettings {
+            game_version: "2026.01.13".to_string(),
+            world_seed: 1,
+            time_of_day: 0.5,
+            weather_seed: 2,
+            world_height: 320,
+            required_assets: Some(vec![asset("Sky.png")]),
+        };
+        assert_eq!(relay(&mut state, &settings).unwrap(), "WorldSettings");
+        assert_eq!(state, ConnectionState::Setup);
+
+        let request = RequestAssets {
+            assets: Some(vec![asset("Sky.png")]),
+        };
+        relay(&mut state, &request).unwrap();
+        let initialize = AssetInitialize {
+            asset: asset("Sky.png"),
+            size: 4,
+        };
+        assert_eq!(relay(&mut state, &initialize).unwrap(), "AssetInitialize");
+        let part = AssetPart {
+            part: Some(Bytes::from_static(&[1, 2, 3, 4])),
+        };
+        relay(&mut state, &part).unwrap();
+        relay(&mut state, &AssetFinalize).unwrap();
+        let progress = WorldLoadProgress {
+            percent_complete: 50,
+            percent_complete_subitem: 0,
+            status: None,
+        };
+        assert_eq!(relay(&mut state, &progress).unwrap(), "WorldLoadProgress");
+        relay(&mut state, &WorldLoadFinished {}).unwrap();
+        let skin = PlayerOptions { skin: None };
+        relay(&mut state, &skin).unwrap();
+        relay(&mut state, &RequestCommonAssetsRebuild {}).unwrap();
+        relay(&mut state, &SetClientId { client_id: 3 }).unwrap();
+        relay(&mut state, &ViewRadius { value: 12 }).unwrap();
+        relay(&mut state, &SetEntitySeed { entity_seed: 9 }).unwrap();
+        let rate = SetUpdateRate {
+            updates_per_second: 30.0,
+        };
+        relay(&mut state, &rate).unwrap();
+        relay(&mut state, &SetTimeDilation { time_dilation: 1.0 }).unwrap();
+        let features = UpdateFeatures {
+            features: Some(vec![(0, true)]),
+        };
+        relay(&mut state, &features).unwrap();
+        let movement = ClientMovement {
+            position_x: 0.0,
+            position_y: 64.0,
+            position_z: 0.0,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            velocity_z: 0.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            on_ground: true,
+        };
+        // Not in game yet
+        assert!(matches!(
+            relay(&mut state, &movement),
+            Err(CodecError::ProtocolViolation {
+                state: ConnectionState::Setup,
+                ..
+            })
+        ));
+        let join = JoinWorld {
+            clear_world: true,
+            fade_in_out: true,
+            world_uuid: Uuid::from_u128(2),
+        };
+        relay(&mut state, &join).unwrap();
+        assert_eq!(state, ConnectionState::Play);
+
+        // 24 is ClientReady now
+        assert_eq!(relay(&mut state, &ClientReady).unwrap(), "ClientReady");
+        relay(&mut state, &movement).unwrap();
+        relay(&mut state, &Ping { time: 1 }).unwrap();
+        assert!(matches!(
+            relay(&mut state, &token),
+            Err(CodecError::ProtocolViolation {
+                state: ConnectionState::Play,
+                ..
+            })
+        ));
+        assert_eq!(state, ConnectionState::Play);
+    }
+
+    #[test]
+    fn shared_ids_need_the_phase() {
+        let progress = WorldLoadProgress {
+            percent_complete: 50,
+            percent_complete_subitem: 0,
+            status: None,
+        };
+        let mut frame = encode_packet(&progress).unwrap();
+        let (id, payload) = split_frame(&mut frame, 1024).unwrap().unwrap();
+        let mut warnings = Vec::new();
+        let options = DecodeOptions::STRICT;
+
+        assert!(matches!(
+            crate::deserialize_client_bound(id, payload.clone()),
+            Err(CodecError::AmbiguousPacketId { id: 21, .. })
+        ));
+        let decode = |state| {
+            deserialize_client_bound_in(state, id, payload.clone(), options, &mut Vec::new())
+        };
+        assert!(matches!(
+            decode(ConnectionState::Setup),
+            Ok(ClientBoundPacket::WorldLoadProgress(decoded)) if decoded == progress
+        ));
+        // Before setup 21 is WorldSettings, which this payload is not
+        assert!(decode(ConnectionState::Auth).is_err());
+        assert!(matches!(
+            decode(ConnectionState::Play),
+            Err(CodecError::ProtocolViolation { id: 21, .. })
+        ));
+
+        // Following the server's packets alone moves past WorldSettings
+        let mut state = ConnectionState::Auth;
+        state.observe(PacketDirection::Clientbound, AuthGrant::PACKET_ID);
+        assert_eq!(state, ConnectionState::Auth);
+        state.observe(PacketDirection::Clientbound, id);
+        assert_eq!(state, ConnectionState::Setup);
+        assert!(matches!(
+            state.decode_client_bound_with(id, payload, options, &mut warnings),
+            Ok(ClientBoundPacket::WorldLoadProgress(_))
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+//! Protocol versions and negotiation of the `Connect` handshake.
+//!
+//! Every Hytale release sends a different `Connect.protocol_hash`. A server
+//! lists the releases it accepts in a `ProtocolVersions` table, each with the
+//! `PacketLayout` its packets use; the layout of the version negotiated with a
+//! client picks the readers and writers for the rest of its connection. Clients
+//! on any other release are turned away with a `Disconnect` explaining why.
+//!
+//! The crate ships no table of release hashes: they are not published, so a
+//! server registers the ones it has checked against the matching client.
+
+use crate::client_bound::ClientBoundPacket;
+use crate::codec::{CodecResult, DecodeOptions, DecodeWarning, Packet, encode_packet};
+use crate::packets::connection::{Connect, Disconnect, DisconnectType};
+use crate::server_bound::ServerBoundPacket;
+use crate::state::ConnectionState;
+use bytes::{Bytes, BytesMut};
+
+/// Packet layouts implemented by this crate.
+///
+/// Releases whose packets didn't change share a layout. A layout added for
+/// another release gets its own arm in the methods below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum PacketLayout {
+    /// The packets of `crate::packets`.
+    #[default]
+    V1,
+}
+
+impl PacketLayout {
+    /// Layout of the packets in `crate::packets`.
+    pub const CURRENT: PacketLayout = PacketLayout::V1;
+
+    /// Decode a server bound packet with this layout's readers, see
+    /// `ConnectionState::decode_server_bound_with`.
+    pub fn decode_server_bound(
+        self,
+        state: &mut ConnectionState,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<ServerBoundPacket> {
+        match self {
+            PacketLayout::V1 => {
+                state.decode_server_bound_with(packet_id, payload, options, warnings)
+            }
+        }
+    }
+
+    /// Decode a client bound packet with this layout's readers, see
+    /// `ConnectionState::decode_client_bound_with`.
+    pub fn decode_client_bound(
+        self,
+        state: &mut ConnectionState,
+        packet_id: u32,
+        payload: Bytes,
+        options: DecodeOptions,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> CodecResult<ClientBoundPacket> {
+        match self {
+            PacketLayout::V1 => {
+                state.decode_client_bound_with(packet_id, payload, options, warnings)
+            }
+        }
+    }
+
+    /// Encode a packet into a complete frame with this layout's writers.
+    pub fn encode<P: Packet>(self, packet: &P) -> CodecResult<BytesMut> {
+        match self {
+            PacketLayout::V1 => encode_packet(packet),
+        }
+    }
+}
+
+/// A protocol revision.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolVersion {
+    /// Hash sent by the client in `Connect.protocol_hash`.
+    pub hash: String,
+    /// Game version, as sent by the server in `WorldSettings.game_version`.
+    pub game_version: String,
+    /// Layout of the release's packets.
+    pub layout: PacketLayout,
+}
+
+impl ProtocolVersion {
+    pub fn new(
+        hash: impl Into<String>,
+        game_version: impl Into<String>,
+        layout: PacketLayout,
+    ) -> Self {
+        Self {
+            hash: hash.into(),
+            game_version: game_version.into(),
+            layout,
+        }
+    }
+}
+
+/// Table of the protocol versions a server accepts, with the one it is running.
+#[derive(Debug, Clone)]
+pub struct ProtocolVersions {
+    current: ProtocolVersion,
+    accepted: Vec<ProtocolVersion>,
+    /// Versions that are not accepted but named in `Disconnect` reasons.
+    known: Vec<ProtocolVersion>,
+}
+
+impl ProtocolVersions {
+    /// Table accepting only the version the server is running.
+    pub fn new(current: ProtocolVersion) -> Self {
+        Self {
+            accepted: vec![current.clone()],
+            current,
+            known: Vec::new(),
+        }
+    }
+
+    /// Also accept clients on `version`, served with its `layout`.
+    pub fn with_version(mut self, version: ProtocolVersion) -> Self {
+        self.known.retain(|known| known.hash != version.hash);
+        self.accepted
+            .retain(|accepted| accepted.hash != version.hash);
+        self.accepted.push(version);
+        self
+    }
+
+    /// Name `version` when rejecting its clients.
+    pub fn with_known_version(mut self, version: ProtocolVersion) -> Self {
+        if self.accepts(&version.hash) {
+            return self;
+        }
+        self.known.retain(|known| known.hash != version.hash);
+        self.known.push(version);
+        self
+    }
+
+    pub fn current(&self) -> &ProtocolVersion {
+        &self.current
+    }
+
+    pub fn accepts(&self, hash: &str) -> bool {
+        self.accepted.iter().any(|version| version.hash == hash)
+    }
+
+    /// An accepted version by hash.
+    pub fn by_hash(&self, hash: &str) -> Option<&ProtocolVersion> {
+        self.accepted.iter().find(|version| version.hash == hash)
+    }
+
+    /// An accepted version by `WorldSettings.game_version`.
+    pub fn by_game_version(&self, game_version: &str) -> Option<&ProtocolVersion> {
+        self.accepted
+            .iter()
+            .find(|version| version.game_version == game_version)
+    }
+
+    /// Accepted versions.
+    pub fn iter(&self) -> impl Iterator<Item = &ProtocolVersion> {
+        self.accepted.iter()
+    }
+
+    /// Pick the version of a connecting client.
+    ///
+    /// Returns the `Disconnect` to send back when the client is rejected.
+    pub fn negotiate(&self, connect: &Connect) -> Result<&ProtocolVersion, Disconnect> {
+        let hash = connect.protocol_hash.as_str();
+        if let Some(version) = self.by_hash(hash) {
+            return Ok(version);
+        }
+        let reason = match self.known.iter().find(|version| version.hash == hash) {
+            Some(version) => format!(
+                "Incompatible game version {}, this server is running {}",
+                version.game_version, self.current.game_version
+            ),
+            None => format!(
+                "Unknown protocol version {hash}, this server is running {}",
+                self.current.game_version
+            ),
+        };
+        Err(Disconnect {
+            reason: Some(reason),
+            disconnect_type: DisconnectType::Kick,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packets::connection::ClientType;
+    use uuid::Uuid;
+
+    fn connect(hash: &str) -> Connect {
+        Connect {
+            protocol_hash: hash.to_string(),
+            client_type: ClientType::Game,
+            language: None,
+            identity_token: None,
+            uuid: Uuid::nil(),
+            username: "Player".to_string(),
+            referral_data: None,
+            referral_source: None,
+        }
+    }
+
+    fn version(hash: &str, game_version: &str) -> ProtocolVersion {
+        ProtocolVersion::new(hash.repeat(64), game_version, PacketLayout::V1)
+    }
+
+    fn versions() -> ProtocolVersions {
+        ProtocolVersions::new(version("b", "2026.01.13"))
+            .with_version(version("a", "2026.01.09"))
+            .with_known_version(version("c", "2025.12.20"))
+    }
+
+    #[test]
+    fn accepts_listed_versions() {
+        let versions = versions();
+        assert_eq!(
+            versions
+                .negotiate(&connect(&"b".repeat(64)))
+                .unwrap()
+                .game_version,
+            "2026.01.13"
+        );
+        assert_eq!(
+            versions
+                .negotiate(&connect(&"a".repeat(64)))
+                .unwrap()
+                .game_version,
+            "2026.01.09"
+        );
+        assert_eq!(
+            versions.by_game_version("2026.01.09").unwrap().hash,
+            "a".repeat(64)
+        );
+        assert_eq!(versions.iter().count(), 2);
+        assert!(
+            versions
+                .iter()
+                .all(|version| version.layout == PacketLayout::CURRENT)
+        );
+    }
+
+    #[test]
+    fn rejects_other_versions_with_a_reason() {
+        let versions = versions();
+        let disconnect = versions.negotiate(&connect(&"c".repeat(64))).unwrap_err();
+        assert_eq!(disconnect.disconnect_type, DisconnectType::Kick);
+        assert_eq!(
+            disconnect.reason.as_deref(),
+            Some("Incompatible game version 2025.12.20, this server is running 2026.01.13")
+        );
+
+        let disconnect = versions.negotiate(&connect("unknown")).unwrap_err();
+        assert_eq!(
+            disconnect.reason.as_deref(),
+            Some("Unknown protocol version unknown, this server is running 2026.01.13")
+        );
+    }
+
+    #[test]
+    fn accepting_a_known_version_moves_it() {
+        let versions = versions().with_version(version("c", "2025.12.20"));
+        assert!(versions.accepts(&"c".repeat(64)));
+        assert!(versions.negotiate(&connect(&"c".repeat(64))).is_ok());
+        let versions = versions.with_known_version(version("c", "x"));
+        assert!(versions.accepts(&"c".repeat(64)));
+    }
+}
@@ -0,0 +1,188 @@
+use bytes::{Bytes, BytesMut};
+use hytale_protocol::HytalePacket;
+use hytale_protocol::codec::{CodecError, DecodeOptions, PacketBuffer, PacketRead, PacketWrite};
+use hytale_protocol::packets::assets::BlockTextures;
+use uuid::Uuid;
+
+fn write<T: PacketWrite>(value: &T) -> BytesMut {
+    let mut buf = BytesMut::new();
+    value.write(&mut buf);
+    buf
+}
+
+/// Read `T` strictly, requiring every byte to be consumed.
+fn read<T: PacketRead>(data: &[u8]) -> Result<T, CodecError> {
+    let mut buf = PacketBuffer::with_options(Bytes::copy_from_slice(data), DecodeOptions::STRICT);
+    let value = T::read(&mut buf)?;
+    buf.finish()?;
+    Ok(value)
+}
+
+fn round_trip<T: PacketRead + PacketWrite + PartialEq + std::fmt::Debug>(value: &T) -> BytesMut {
+    let data = write(value);
+    assert_eq!(&read::<T>(&data).unwrap(), value);
+    data
+}
+
+#[derive(Debug, PartialEq, HytalePacket)]
+struct Nullable {
+    #[hytale(nullable)]
+    id: Option<i32>,
+    #[hytale(nullable)]
+    uuid: Option<Uuid>,
+    #[hytale(nullable, var_string)]
+    name: Option<String>,
+    flag: bool,
+}
+
+#[test]
+fn nullable_fields() {
+    let data = round_trip(&Nullable {
+        id: Some(7),
+        uuid: None,
+        name: Some("a".to_string()),
+        flag: true,
+    });
+    // nullBits, id, zero filled uuid, name, flag
+    assert_eq!(data[0], 0b101);
+    assert_eq!(&data[1..5], 7i32.to_le_bytes());
+    assert_eq!(&data[5..21], [0; 16]);
+    assert_eq!(&data[21..], [1, b'a', 1]);
+
+    let data = round_trip(&Nullable {
+        id: None,
+        uuid: Some(Uuid::from_u128(1)),
+        name: None,
+        flag: false,
+    });
+    assert_eq!(data[0], 0b010);
+    assert_eq!(data.len(), 1 + 4 + 16 + 1);
+}
+
+#[derive(Debug, PartialEq, HytalePacket)]
+struct Offsets {
+    version: u16,
+    #[hytale(nullable, offset, var_string)]
+    first: Option<String>,
+    #[hytale(offset, var_string)]
+    second: String,
+    #[hytale(nullable, offset)]
+    blob: Option<Bytes>,
+    #[hytale(offset)]
+    list: Vec<Inner>,
+    #[hytale(be)]
+    port: u16,
+}
+
+#[derive(Debug, PartialEq, HytalePacket)]
+struct Inner {
+    #[hytale(fixed_size = 4)]
+    tag: Vec<u8>,
+    value: f64,
+}
+
+#[test]
+fn offset_fields() {
+    let offsets = Offsets {
+        version: 3,
+        first: None,
+        second: "two".to_string(),
+        blob: Some(Bytes::from_static(&[9, 8, 7])),
+        list: vec![Inner {
+            tag: vec![1, 2],
+            value: 0.5,
+        }],
+        port: 5520,
+    };
+    let data = write(&offsets);
+    // nullBits, inline fields in order, then the offset table
+    assert_eq!(data[0], 0b10);
+    assert_eq!(&data[1..3], 3u16.to_le_bytes());
+    assert_eq!(&data[3..5], 5520u16.to_be_bytes());
+    let table: Vec<i32> = data[5..21]
+        .chunks(4)
+        .map(|offset| i32::from_le_bytes(offset.try_into().unwrap()))
+        .collect();
+    assert_eq!(table, [-1, 0, 4, 8]);
+    assert_eq!(&data[21..25], [3, b't', b'w', b'o']);
+    assert_eq!(&data[29..33], [1, 1, 2, 0]);
+
+    let decoded: Offsets = read(&data).unwrap();
+    assert_eq!(decoded.list[0].tag, [1, 2, 0, 0]);
+    assert_eq!(decoded.second, offsets.second);
+    assert_eq!(decoded.blob, offsets.blob);
+    assert_eq!(decoded.port, 5520);
+}
+
+#[test]
+fn offsets_past_the_end_are_rejected() {
+    let mut data = write(&Offsets {
+        version: 0,
+        first: Some("x".to_string()),
+        second: String::new(),
+        blob: None,
+        list: Vec::new(),
+        port: 0,
+    });
+    data[5..9].copy_from_slice(&1000i32.to_le_bytes());
+    let error = read::<Offsets>(&data).unwrap_err().to_string();
+    assert!(error.contains("Invalid offset 1000"), "{error}");
+}
+
+const BYTES_LENGTH: usize = 3;
+
+#[derive(Debug, PartialEq, HytalePacket)]
+struct Fixed {
+    #[hytale(fixed_size = 8)]
+    code: String,
+    #[hytale(fixed_size = BYTES_LENGTH)]
+    bytes: Vec<u8>,
+}
+
+#[test]
+fn fixed_size_fields() {
+    let data = round_trip(&Fixed {
+        code: "abc".to_string(),
+        bytes: vec![1, 2, 3],
+    });
+    assert_eq!(&data[..], b"abc\0\0\0\0\0\x01\x02\x03");
+
+    // Longer values are truncated
+    let data = write(&Fixed {
+        code: "abcdefghij".to_string(),
+        bytes: vec![1, 2, 3, 4],
+    });
+    assert_eq!(&data[..], b"abcdefgh\x01\x02\x03");
+}
+
+#[test]
+fn fixed_size_strings_are_cut_on_a_char_boundary() {
+    // 'é' takes bytes 7 and 8, the cut after 8 bytes falls in its middle
+    let data = write(&Fixed {
+        code: "abcdefgé".to_string(),
+        bytes: Vec::new(),
+    });
+    assert_eq!(&data[..8], b"abcdefg\0");
+    let decoded: Fixed = read(&data).unwrap();
+    assert_eq!(decoded.code, "abcdefg");
+}
+
+#[test]
+fn block_textures() {
+    let textures = BlockTextures {
+        top: Some("Top.png".to_string()),
+        bottom: None,
+        ..BlockTextures::uniform("Side.png")
+    };
+    let data = round_trip(&textures);
+    assert_eq!(data[0], 0b111101);
+    assert_eq!(&data[1..5], 1.0f32.to_le_bytes());
+    assert_eq!(&data[5..9], 0i32.to_le_bytes());
+    assert_eq!(&data[9..13], (-1i32).to_le_bytes());
+    assert_eq!(&data[13..17], 8i32.to_le_bytes());
+}
+
+#[test]
+fn rejected_attributes() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,119 @@
+use hytale_protocol::capture::CaptureWriter;
+use hytale_protocol::codec::{Packet, encode_packet};
+use hytale_protocol::common::Asset;
+use hytale_protocol::packets::auth::AuthGrant;
+use hytale_protocol::packets::connection::{ClientType, Connect, PROTOCOL_HASH_LENGTH};
+use hytale_protocol::packets::setup::WorldSettings;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// A capture of the start of a login.
+fn capture() -> Vec<u8> {
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer
+        .record_at(
+            Duration::from_millis(1),
+            Connect::DIRECTION,
+            &encode_packet(&Connect {
+                protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+                client_type: ClientType::Game,
+                language: None,
+                identity_token: None,
+                uuid: Uuid::from_u128(1),
+                username: "Player".to_string(),
+                referral_data: None,
+                referral_source: None,
+            })
+            .unwrap(),
+        )
+        .unwrap();
+    writer
+        .record_packet(&AuthGrant {
+            authorization_grant: Some("grant".to_string()),
+            server_identity_token: None,
+        })
+        .unwrap();
+    writer
+        .record_packet(&WorldSettings {
+            game_version: "2026.01.13".to_string(),
+            world_seed: 1,
+            time_of_day: 0.5,
+            weather_seed: 2,
+            world_height: 320,
+            required_assets: Some(
+                (0..100)
+                    .map(|i| Asset {
+                        hash: format!("{i:0>64}"),
+                        name: format!("Asset{i}.png"),
+                    })
+                    .collect(),
+            ),
+        })
+        .unwrap();
+    writer.into_inner()
+}
+
+/// Run `hytale-dump` on a capture fed through stdin.
+fn dump(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hytale-dump"))
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Fails if the dump gave up before reading everything
+    let _ = child.stdin.take().unwrap().write_all(input);
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn filters_by_id() {
+    let output = dump(&["--filter", "21"], &capture());
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let [line] = &stdout.lines().collect::<Vec<_>>()[..] else {
+        panic!("{stdout}")
+    };
+    assert!(line.contains("<- WorldSettings (21)"), "{line}");
+    assert!(line.contains("decompressed"), "{line}");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_lines() {
+    let output = dump(&["--json", "--filter", "connect,WorldSettings"], &capture());
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let [connect, settings] = &lines[..] else {
+        panic!("{stdout}")
+    };
+
+    assert_eq!(connect["name"], "Connect");
+    assert_eq!(connect["direction"], "server_bound");
+    assert_eq!(connect["timestamp"], 0.001);
+    assert_eq!(connect["decompressed_size"], serde_json::Value::Null);
+    assert_eq!(connect["fields"]["username"], "Player");
+
+    assert_eq!(settings["name"], "WorldSettings");
+    assert_eq!(settings["id"], 21);
+    assert_eq!(settings["fields"]["world_height"], 320);
+    let size = settings["size"].as_u64().unwrap();
+    let decompressed_size = settings["decompressed_size"].as_u64().unwrap();
+    assert!(size < decompressed_size, "{settings}");
+}
+
+#[cfg(not(feature = "serde"))]
+#[test]
+fn json_needs_serde() {
+    let output = dump(&["--json"], &capture());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("serde"));
+}
@@ -0,0 +1,467 @@
+use hytale_protocol::capture::CapturedPacket;
+use hytale_protocol::codec::{CodecError, encode_packet};
+use hytale_protocol::packets::auth::{AuthGrant, AuthToken};
+use hytale_protocol::packets::connection::{ClientType, Connect, PROTOCOL_HASH_LENGTH};
+use hytale_protocol::packets::player::ClientMovement;
+use hytale_protocol::pcap::{PcapImporter, PcapTraffic};
+use hytale_protocol::{ClientBoundPacket, ConnectionState, PacketDirection, ServerBoundPacket};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use uuid::Uuid;
+
+const SYN: u8 = 0x02;
+const PSH_ACK: u8 = 0x18;
+
+fn server_v4() -> SocketAddr {
+    "10.0.0.1:5520".parse().unwrap()
+}
+
+fn client_v4() -> SocketAddr {
+    "10.0.0.2:50000".parse().unwrap()
+}
+
+fn server_v6() -> SocketAddr {
+    "[fd00::1]:5520".parse().unwrap()
+}
+
+fn client_v6() -> SocketAddr {
+    "[fd00::2]:50000".parse().unwrap()
+}
+
+/// Link layer the IP packets are wrapped in.
+#[derive(Debug, Clone, Copy)]
+enum Link {
+    Ethernet,
+    /// Linux cooked capture v1.
+    Sll,
+    /// Linux cooked capture v2.
+    Sll2,
+    /// BSD loopback, address family in host byte order.
+    Null,
+    /// OpenBSD loopback, address family in network byte order.
+    Loop,
+    Raw,
+}
+
+impl Link {
+    fn link_type(self) -> u32 {
+        match self {
+            Link::Ethernet => 1,
+            Link::Sll => 113,
+            Link::Sll2 => 276,
+            Link::Null => 0,
+            Link::Loop => 108,
+            Link::Raw => 101,
+        }
+    }
+
+    fn frame(self, ip: &[u8]) -> Vec<u8> {
+        let v6 = ip[0] >> 4 == 6;
+        let ether_type: [u8; 2] = if v6 { [0x86, 0xDD] } else { [0x08, 0x00] };
+        let family: u32 = if v6 { 30 } else { 2 };
+        let mut frame = match self {
+            Link::Ethernet => [&[0; 12][..], &ether_type].concat(),
+            Link::Sll => {
+                // Sent by us, ARPHRD_ETHER, 6 bytes of address
+                [&[0, 4, 0, 1, 0, 6][..], &[0; 8], &ether_type].concat()
+            }
+            Link::Sll2 => [
+                &ether_type[..],
+                &[0; 2],
+                &[0, 0, 0, 1],
+                &[0, 1, 4, 6],
+                &[0; 8],
+            ]
+            .concat(),
+            Link::Null => family.to_le_bytes().to_vec(),
+            Link::Loop => family.to_be_bytes().to_vec(),
+            Link::Raw => Vec::new(),
+        };
+        frame.extend_from_slice(ip);
+        frame
+    }
+}
+
+/// An IPv4 or IPv6 packet carrying a TCP segment.
+fn ip_packet(
+    source: SocketAddr,
+    destination: SocketAddr,
+    sequence: u32,
+    flags: u8,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut packet = match (source.ip(), destination.ip()) {
+        (IpAddr::V4(source), IpAddr::V4(destination)) => {
+            // IPv4, don't fragment
+            let mut packet = vec![0x45, 0];
+            packet.extend_from_slice(&((40 + payload.len()) as u16).to_be_bytes());
+            packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+            packet.extend_from_slice(&source.octets());
+            packet.extend_from_slice(&destination.octets());
+            packet
+        }
+        (IpAddr::V6(source), IpAddr::V6(destination)) => {
+            let mut packet = vec![0x60, 0, 0, 0];
+            packet.extend_from_slice(&((20 + payload.len()) as u16).to_be_bytes());
+            packet.extend_from_slice(&[6, 64]);
+            packet.extend_from_slice(&source.octets());
+            packet.extend_from_slice(&destination.octets());
+            packet
+        }
+        _ => panic!("mixed IP versions"),
+    };
+    // TCP
+    packet.extend_from_slice(&source.port().to_be_bytes());
+    packet.extend_from_slice(&destination.port().to_be_bytes());
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0, 0x50, flags, 0xFF, 0xFF, 0, 0, 0, 0]);
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/// Something TCP segments can be written to, one per second.
+trait Capture {
+    fn link(&self) -> Link;
+
+    /// Record a link layer frame captured `time` seconds after the epoch.
+    fn record(&mut self, time: u32, frame: &[u8]);
+
+    fn time(&mut self) -> &mut u32;
+
+    fn segment(
+        &mut self,
+        source: SocketAddr,
+        destination: SocketAddr,
+        sequence: u32,
+        flags: u8,
+        payload: &[u8],
+    ) {
+        let frame = self
+            .link()
+            .frame(&ip_packet(source, destination, sequence, flags, payload));
+        *self.time() += 1;
+        let time = *self.time();
+        self.record(time, &frame);
+    }
+}
+
+/// Writes a classic pcap, microsecond timestamps.
+struct Pcap {
+    data: Vec<u8>,
+    link: Link,
+    time: u32,
+}
+
+impl Pcap {
+    fn new(link: Link) -> Self {
+        let mut data = Vec::new();
+        data.extend_from_slice(&0xA1B2C3D4u32.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&65535u32.to_le_bytes());
+        data.extend_from_slice(&link.link_type().to_le_bytes());
+        Self {
+            data,
+            link,
+            time: 0,
+        }
+    }
+}
+
+impl Capture for Pcap {
+    fn link(&self) -> Link {
+        self.link
+    }
+
+    fn time(&mut self) -> &mut u32 {
+        &mut self.time
+    }
+
+    fn record(&mut self, time: u32, frame: &[u8]) {
+        self.data.extend_from_slice(&time.to_le_bytes());
+        self.data.extend_from_slice(&0u32.to_le_bytes());
+        self.data
+            .extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        self.data
+            .extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(frame);
+    }
+}
+
+/// Writes a big endian pcapng with a single interface.
+///
+/// Frames go in Enhanced Packet Blocks with nanosecond timestamps (`if_tsresol`
+/// 9), or in Simple Packet Blocks, which have none, with `simple`.
+struct Pcapng {
+    data: Vec<u8>,
+    link: Link,
+    time: u32,
+    simple: bool,
+}
+
+impl Pcapng {
+    fn new(link: Link) -> Self {
+        let mut pcapng = Self {
+            data: Vec::new(),
+            link,
+            time: 0,
+            simple: false,
+        };
+        // Section Header: byte order magic, version 1.0, unknown section length
+        let mut section = 0x1A2B3C4Du32.to_be_bytes().to_vec();
+        section.extend_from_slice(&[0, 1, 0, 0]);
+        section.extend_from_slice(&[0xFF; 8]);
+        pcapng.block(0x0A0D0D0A, &section);
+        // Interface Description: if_tsresol = 9, then opt_endofopt
+        let mut interface = (link.link_type() as u16).to_be_bytes().to_vec();
+        interface.extend_from_slice(&[0, 0]);
+        interface.extend_from_slice(&65535u32.to_be_bytes());
+        interface.extend_from_slice(&[0, 9, 0, 1, 9, 0, 0, 0]);
+        interface.extend_from_slice(&[0, 0, 0, 0]);
+        pcapng.block(1, &interface);
+        pcapng
+    }
+
+    fn simple(mut self) -> Self {
+        self.simple = true;
+        self
+    }
+
+    fn block(&mut self, block_type: u32, body: &[u8]) {
+        let padding = body.len().next_multiple_of(4) - body.len();
+        let length = (12 + body.len() + padding) as u32;
+        self.data.extend_from_slice(&block_type.to_be_bytes());
+        self.data.extend_from_slice(&length.to_be_bytes());
+        self.data.extend_from_slice(body);
+        self.data.extend_from_slice(&[0; 3][..padding]);
+        self.data.extend_from_slice(&length.to_be_bytes());
+    }
+}
+
+impl Capture for Pcapng {
+    fn link(&self) -> Link {
+        self.link
+    }
+
+    fn time(&mut self) -> &mut u32 {
+        &mut self.time
+    }
+
+    fn record(&mut self, time: u32, frame: &[u8]) {
+        let length = (frame.len() as u32).to_be_bytes();
+        if self.simple {
+            self.block(3, &[&length[..], frame].concat());
+            return;
+        }
+        let ticks = time as u64 * 1_000_000_000;
+        let mut body = 0u32.to_be_bytes().to_vec();
+        body.extend_from_slice(&((ticks >> 32) as u32).to_be_bytes());
+        body.extend_from_slice(&(ticks as u32).to_be_bytes());
+        body.extend_from_slice(&length);
+        body.extend_from_slice(&length);
+        body.extend_from_slice(frame);
+        self.block(6, &body);
+    }
+}
+
+fn connect() -> Vec<u8> {
+    encode_packet(&Connect {
+        protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+        client_type: ClientType::Game,
+        language: None,
+        identity_token: None,
+        uuid: Uuid::from_u128(1),
+        username: "Player".to_string(),
+        referral_data: None,
+        referral_source: None,
+    })
+    .unwrap()
+    .to_vec()
+}
+
+fn grant() -> Vec<u8> {
+    encode_packet(&AuthGrant {
+        authorization_grant: Some("grant".to_string()),
+        server_identity_token: None,
+    })
+    .unwrap()
+    .to_vec()
+}
+
+fn token() -> Vec<u8> {
+    encode_packet(&AuthToken {
+        access_token: Some("access".to_string()),
+        server_authorization_grant: None,
+    })
+    .unwrap()
+    .to_vec()
+}
+
+/// The start of a login, out of order and with retransmissions.
+///
+/// Connect completes at second 4, AuthGrant at 5 and AuthToken at 9.
+fn login(capture: &mut impl Capture, client: SocketAddr, server: SocketAddr) {
+    let connect = connect();
+    let grant = grant();
+    let token = token();
+
+    capture.segment(client, server, 1000, SYN, &[]);
+    capture.segment(server, client, 5000, SYN, &[]);
+    // Connect split in two, the second half arriving first
+    let (first, second) = connect.split_at(20);
+    capture.segment(client, server, 1021, PSH_ACK, second);
+    capture.segment(client, server, 1001, PSH_ACK, first);
+    // Retransmissions of segments already seen
+    capture.segment(server, client, 5001, PSH_ACK, &grant);
+    capture.segment(server, client, 5001, PSH_ACK, &grant);
+    capture.segment(client, server, 1001, PSH_ACK, &connect);
+    // A short segment, then a retransmission overlapping it
+    let token_at = 1001 + connect.len() as u32;
+    capture.segment(client, server, token_at, PSH_ACK, &token[..3]);
+    capture.segment(client, server, token_at, PSH_ACK, &token);
+}
+
+/// `(timestamp, direction, name)` of the imported packets, checking their addresses.
+fn packets(
+    traffic: &PcapTraffic,
+    client: SocketAddr,
+    server: SocketAddr,
+) -> Vec<(Duration, PacketDirection, &'static str)> {
+    assert!(traffic.errors.is_empty(), "{:?}", traffic.errors);
+    traffic
+        .packets
+        .iter()
+        .map(|packet| {
+            assert_eq!(packet.client, client);
+            assert_eq!(packet.server, server);
+            // None of them is compressed
+            assert_eq!(packet.decompressed_size, packet.payload.len());
+            let name = match &packet.packet {
+                CapturedPacket::ServerBound(packet) => packet.name(),
+                CapturedPacket::ClientBound(packet) => packet.name(),
+            };
+            (packet.timestamp, packet.direction, name)
+        })
+        .collect()
+}
+
+fn login_packets(seconds: [u64; 3]) -> [(Duration, PacketDirection, &'static str); 3] {
+    [
+        (
+            Duration::from_secs(seconds[0]),
+            PacketDirection::Serverbound,
+            "Connect",
+        ),
+        (
+            Duration::from_secs(seconds[1]),
+            PacketDirection::Clientbound,
+            "AuthGrant",
+        ),
+        (
+            Duration::from_secs(seconds[2]),
+            PacketDirection::Serverbound,
+            "AuthToken",
+        ),
+    ]
+}
+
+#[test]
+fn reassembles_and_skips_broken_connections() {
+    let server = server_v4();
+    let client = client_v4();
+    let broken: SocketAddr = "10.0.0.2:50001".parse().unwrap();
+    let connect = connect();
+    let token = token();
+    let movement = encode_packet(&ClientMovement {
+        position_x: 0.0,
+        position_y: 64.0,
+        position_z: 0.0,
+        velocity_x: 0.0,
+        velocity_y: 0.0,
+        velocity_z: 0.0,
+        yaw: 0.0,
+        pitch: 0.0,
+        on_ground: true,
+    })
+    .unwrap();
+
+    let mut pcap = Pcap::new(Link::Ethernet);
+    login(&mut pcap, client, server);
+    // Moving before connecting is out of phase, the rest of that connection is skipped
+    pcap.segment(broken, server, 7000, SYN, &[]);
+    pcap.segment(broken, server, 7001, PSH_ACK, &movement);
+    pcap.segment(
+        broken,
+        server,
+        7001 + movement.len() as u32,
+        PSH_ACK,
+        &connect,
+    );
+
+    let mut traffic = PcapImporter::new().import(&pcap.data[..]).unwrap();
+    let errors = std::mem::take(&mut traffic.errors);
+    assert_eq!(packets(&traffic, client, server), login_packets([4, 5, 9]));
+    assert!(matches!(
+        &traffic.packets[0].packet,
+        CapturedPacket::ServerBound(ServerBoundPacket::Connect(connect)) if connect.username == "Player"
+    ));
+    assert!(matches!(
+        &traffic.packets[1].packet,
+        CapturedPacket::ClientBound(ClientBoundPacket::AuthGrant(grant))
+            if grant.authorization_grant.as_deref() == Some("grant")
+    ));
+    assert_eq!(traffic.packets[2].payload[..], token[8..]);
+
+    let [error] = &errors[..] else {
+        panic!("{errors:?}")
+    };
+    assert_eq!(error.client, broken);
+    assert_eq!(error.direction, PacketDirection::Serverbound);
+    assert_eq!(error.timestamp, Duration::from_secs(11));
+    assert!(matches!(
+        error.error,
+        CodecError::ProtocolViolation {
+            state: ConnectionState::Handshake,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn link_layers() {
+    for link in [
+        Link::Ethernet,
+        Link::Sll,
+        Link::Sll2,
+        Link::Null,
+        Link::Loop,
+        Link::Raw,
+    ] {
+        for (client, server) in [(client_v4(), server_v4()), (client_v6(), server_v6())] {
+            let mut pcap = Pcap::new(link);
+            login(&mut pcap, client, server);
+            let traffic = PcapImporter::new().import(&pcap.data[..]).unwrap();
+            assert_eq!(
+                packets(&traffic, client, server),
+                login_packets([4, 5, 9]),
+                "{link:?} {client}"
+            );
+        }
+    }
+}
+
+#[test]
+fn pcapng_blocks() {
+    for (client, server) in [(client_v4(), server_v4()), (client_v6(), server_v6())] {
+        let mut pcapng = Pcapng::new(Link::Ethernet);
+        login(&mut pcapng, client, server);
+        let traffic = PcapImporter::new().import(&pcapng.data[..]).unwrap();
+        assert_eq!(packets(&traffic, client, server), login_packets([4, 5, 9]));
+
+        let mut pcapng = Pcapng::new(Link::Sll2).simple();
+        login(&mut pcapng, client, server);
+        let traffic = PcapImporter::new().import(&pcapng.data[..]).unwrap();
+        assert_eq!(packets(&traffic, client, server), login_packets([0, 0, 0]));
+    }
+}
@@ -0,0 +1,150 @@
+use hytale_protocol::codec::{CodecError, Packet, encode_packet, encode_raw_packet};
+use hytale_protocol::common::Asset;
+use hytale_protocol::compression::Compressor;
+use hytale_protocol::packets::connection::{ClientType, Connect, PROTOCOL_HASH_LENGTH};
+use hytale_protocol::packets::interface::{ChatMessage, ChatType};
+use hytale_protocol::packets::setup::WorldSettings;
+use hytale_protocol::proxy::{PacketFilter, Proxy, ProxyConnection};
+use hytale_protocol::{PacketDirection, ServerBoundPacket};
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Shouts every chat message and remembers the frames that failed to decode.
+#[derive(Default)]
+struct Shout {
+    undecodable: Mutex<Vec<(PacketDirection, u32)>>,
+}
+
+impl PacketFilter for Shout {
+    fn server_bound(
+        &self,
+        _: &ProxyConnection,
+        packet: ServerBoundPacket,
+    ) -> Option<ServerBoundPacket> {
+        match packet {
+            ServerBoundPacket::ChatMessage(message) => {
+                Some(ServerBoundPacket::ChatMessage(ChatMessage {
+                    message: message.message.to_uppercase(),
+                    ..message
+                }))
+            }
+            packet => Some(packet),
+        }
+    }
+
+    fn undecodable(
+        &self,
+        _: &ProxyConnection,
+        direction: PacketDirection,
+        packet_id: u32,
+        _: &CodecError,
+    ) {
+        self.undecodable
+            .lock()
+            .unwrap()
+            .push((direction, packet_id));
+    }
+}
+
+fn chat(message: &str) -> ChatMessage {
+    ChatMessage {
+        message: message.to_string(),
+        chat_type: ChatType::Normal,
+        sender_name: None,
+    }
+}
+
+fn connect(address: std::net::SocketAddr) -> TcpStream {
+    let stream = TcpStream::connect(address).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream
+}
+
+#[test]
+fn forwards_frames_through_a_filter() {
+    let settings = WorldSettings {
+        game_version: "2026.01.13".to_string(),
+        world_seed: 1,
+        time_of_day: 0.5,
+        weather_seed: 2,
+        world_height: 320,
+        required_assets: Some(
+            (0..100)
+                .map(|i| Asset {
+                    hash: format!("{i:064x}"),
+                    name: format!("Common/Blocks/Block_{i}.json"),
+                })
+                .collect(),
+        ),
+    };
+    // Compressed differently from what re-encoding would give
+    let from_server = Compressor::new(19)
+        .unwrap()
+        .encode_packet(&settings)
+        .unwrap();
+    assert_ne!(from_server, encode_packet(&settings).unwrap());
+
+    let connect_frame = encode_packet(&Connect {
+        protocol_hash: "a".repeat(PROTOCOL_HASH_LENGTH),
+        client_type: ClientType::Game,
+        language: None,
+        identity_token: None,
+        uuid: Uuid::from_u128(1),
+        username: "Player".to_string(),
+        referral_data: None,
+        referral_source: None,
+    })
+    .unwrap();
+    let unknown = encode_raw_packet(0x7FFF_0000, false, &[1, 2, 3]).unwrap();
+    // A ChatMessage cut after its null bits
+    let malformed = encode_raw_packet(ChatMessage::PACKET_ID, false, &[0]).unwrap();
+    let unchanged = [&connect_frame[..], &unknown, &malformed].concat();
+    let from_client = [&unchanged[..], &encode_packet(&chat("hello")).unwrap()].concat();
+
+    let upstream = TcpListener::bind("127.0.0.1:0").unwrap();
+    let upstream_address = upstream.local_addr().unwrap();
+    let server = {
+        let from_server = from_server.clone();
+        thread::spawn(move || {
+            let (mut stream, _) = upstream.accept().unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            stream.write_all(&from_server).unwrap();
+            stream.shutdown(Shutdown::Write).unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        })
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_address = listener.local_addr().unwrap();
+    let proxy = Proxy::new(upstream_address, Shout::default());
+    let handle = {
+        let proxy = proxy.clone();
+        thread::spawn(move || proxy.handle(listener.accept().unwrap().0))
+    };
+
+    let mut client = connect(proxy_address);
+    client.write_all(&from_client).unwrap();
+    client.shutdown(Shutdown::Write).unwrap();
+    let mut received = Vec::new();
+    client.read_to_end(&mut received).unwrap();
+
+    handle.join().unwrap().unwrap();
+    assert_eq!(received, from_server);
+
+    let expected = [&unchanged[..], &encode_packet(&chat("HELLO")).unwrap()].concat();
+    assert_eq!(server.join().unwrap(), expected);
+    assert_eq!(
+        *proxy.filter().undecodable.lock().unwrap(),
+        [(PacketDirection::Serverbound, ChatMessage::PACKET_ID)]
+    );
+}
@@ -0,0 +1,9 @@
+use hytale_protocol::HytalePacket;
+
+#[derive(HytalePacket)]
+struct Packet {
+    #[hytale(var_string, be)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use hytale_protocol::HytalePacket;
+
+#[derive(HytalePacket)]
+enum Packet {
+    A,
+}
+
+fn main() {}
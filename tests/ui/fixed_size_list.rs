@@ -0,0 +1,9 @@
+use hytale_protocol::HytalePacket;
+
+#[derive(HytalePacket)]
+struct Packet {
+    #[hytale(fixed_size = 4)]
+    ids: Vec<i32>,
+}
+
+fn main() {}
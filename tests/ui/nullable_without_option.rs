@@ -0,0 +1,9 @@
+use hytale_protocol::HytalePacket;
+
+#[derive(HytalePacket)]
+struct Packet {
+    #[hytale(nullable)]
+    id: i32,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use hytale_protocol::HytalePacket;
+
+#[derive(HytalePacket)]
+struct Packet {
+    id: Option<i32>,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use hytale_protocol::HytalePacket;
+
+#[derive(HytalePacket)]
+struct Packet {
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use hytale_protocol::HytalePacket;
+
+#[derive(HytalePacket)]
+struct Packet(i32);
+
+fn main() {}